@@ -56,16 +56,28 @@ fn spawn_server(ctx: &mut zmq::Context, workers: u64) -> Sender<()> {
     start_tx
 }
 
-fn worker(push_socket: &zmq::Socket, count: u64) {
+fn worker(push_socket: &zmq::Socket, count: u64, msg_size: usize, pool: Option<&zmq::MessagePool>) {
+    // The server parses the payload as a number, so pad it with zeros.
+    let payload = format!("{:0>width$}", 100, width = msg_size);
+
     for _ in 0..count {
-        push_socket.send(&100.to_string(), 0).unwrap();
+        match pool {
+            Some(pool) => push_socket.send(pool.copy_from(payload.as_bytes()), 0),
+            None => push_socket.send(&payload, 0),
+        }
+        .unwrap();
     }
 
     // Let the server know we're done.
     push_socket.send("", 0).unwrap();
 }
 
-fn spawn_worker(ctx: &mut zmq::Context, count: u64) -> Receiver<()> {
+fn spawn_worker(
+    ctx: &mut zmq::Context,
+    count: u64,
+    msg_size: usize,
+    pool: Option<zmq::MessagePool>,
+) -> Receiver<()> {
     let push_socket = ctx.socket(zmq::PUSH).unwrap();
 
     push_socket.connect("inproc://server-pull").unwrap();
@@ -77,7 +89,7 @@ fn spawn_worker(ctx: &mut zmq::Context, count: u64) -> Receiver<()> {
         // Let the main thread we're ready.
         tx.send(()).unwrap();
 
-        worker(&push_socket, count);
+        worker(&push_socket, count, msg_size, pool.as_ref());
 
         tx.send(()).unwrap();
     });
@@ -92,7 +104,7 @@ fn seconds(d: &Duration) -> f64 {
     d.as_secs() as f64 + (f64::from(d.subsec_nanos()) / 1e9)
 }
 
-fn run(ctx: &mut zmq::Context, size: u64, workers: u64) {
+fn run(ctx: &mut zmq::Context, size: u64, workers: u64, msg_size: usize, use_pool: bool) {
     let start_ch = spawn_server(ctx, workers);
 
     // Create some command/control sockets.
@@ -104,10 +116,15 @@ fn run(ctx: &mut zmq::Context, size: u64, workers: u64) {
     //push_socket.connect("tcp://127.0.0.1:3456").unwrap();
     //pull_socket.connect("tcp://127.0.0.1:3457").unwrap();
 
-    // Spawn all the workers.
+    // Spawn all the workers, sharing a pool of message buffers if requested.
+    let pool = if use_pool {
+        Some(zmq::MessagePool::new(msg_size, 8192))
+    } else {
+        None
+    };
     let mut worker_results = Vec::new();
     for _ in 0..workers {
-        worker_results.push(spawn_worker(ctx, size / workers));
+        worker_results.push(spawn_worker(ctx, size / workers, msg_size, pool.clone()));
     }
 
     let start = Instant::now();
@@ -129,6 +146,9 @@ fn run(ctx: &mut zmq::Context, size: u64, workers: u64) {
     println!("Test took {} seconds", elapsed);
     let thruput = ((size / workers * workers) as f64) / elapsed;
     println!("Throughput={} per sec", thruput);
+    if let Some(pool) = pool {
+        println!("Pool stats: {:?}", pool.stats());
+    }
 }
 
 fn main() {
@@ -144,8 +164,13 @@ fn main() {
 
     let size = args[1].parse().unwrap();
     let workers = args[2].parse().unwrap();
+    // Optionally, the size of the messages, and "pool" to send them
+    // using a `MessagePool`. The default size is above the 33 bytes up to
+    // which 0MQ stores messages inline, where pooling does not apply.
+    let msg_size = args.get(3).map_or(64, |arg| arg.parse().unwrap());
+    let use_pool = args.get(4).map(String::as_str) == Some("pool");
 
     let mut ctx = zmq::Context::new();
 
-    run(&mut ctx, size, workers);
+    run(&mut ctx, size, workers, msg_size, use_pool);
 }
@@ -3,7 +3,7 @@
 //! load balancing broker
 //! clients and workers here are shown in process
 
-use zmq::SNDMORE;
+use zmq::{Empty, SNDMORE};
 //use std::time::;
 use std::thread;
 
@@ -157,13 +157,7 @@ fn main() {
                     .expect("backend failed receiving client reply")
                     .unwrap();
                 frontend
-                    .send(&client_id, SNDMORE)
-                    .expect("frontend failed sending client id");
-                frontend
-                    .send("", SNDMORE)
-                    .expect("frontend failed sending empty");
-                frontend
-                    .send(&reply, 0)
+                    .send_typed(&(client_id, Empty, reply), 0)
                     .expect("frontend failed sending reply");
                 client_nbr -= 1;
                 if client_nbr == 0 {
@@ -175,37 +169,14 @@ fn main() {
         if items[1].is_readable() {
             //  Now get next client request, route to last-used worker
             //  Client request is [identity][empty][request]
-            let client_id = frontend
-                .recv_string(0)
-                .expect("frontend failed receiving client id")
-                .unwrap();
-            assert!(
-                frontend
-                    .recv_string(0)
-                    .expect("frontend failed receiving empty")
-                    .unwrap()
-                    == ""
-            );
-            let request = frontend
-                .recv_string(0)
+            let (client_id, Empty, request): (String, Empty, String) = frontend
+                .recv_typed(0)
                 .expect("frontend failed receiving request")
-                .unwrap();
+                .expect("malformed client request");
 
             let worker = worker_queue.pop().unwrap();
             backend
-                .send(&worker, SNDMORE)
-                .expect("backend failed sending worker_id");
-            backend
-                .send("", SNDMORE)
-                .expect("backend failed sending empty");
-            backend
-                .send(client_id.as_bytes(), SNDMORE)
-                .expect("backend failed sending client_id");
-            backend
-                .send("", SNDMORE)
-                .expect("backend faield sending second empty");
-            backend
-                .send(&request, 0)
+                .send_typed(&(worker, Empty, client_id, Empty, request), 0)
                 .expect("backend failed sending request");
         }
     }
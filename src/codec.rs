@@ -0,0 +1,98 @@
+//! Serialization of values into single-frame messages using serde.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use std::error;
+use std::fmt;
+
+#[cfg(feature = "bincode")]
+use bincode_crate as bincode;
+
+use crate::{Error, Message};
+
+/// A serialization format for `Socket::send_serde` and
+/// `Socket::recv_serde`.
+///
+/// Each variant is only available if the corresponding feature, `json`,
+/// `msgpack` or `bincode`, is enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Codec {
+    /// JSON, via `serde_json`.
+    #[cfg(feature = "json")]
+    Json,
+    /// MessagePack, via `rmp-serde`. Structs are encoded as maps with
+    /// the field names as keys, for interoperability with other
+    /// MessagePack implementations.
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+    /// bincode, via `bincode`, with its default configuration.
+    #[cfg(feature = "bincode")]
+    Bincode,
+}
+
+type BoxError = Box<dyn error::Error + Send + Sync>;
+
+impl Codec {
+    pub(crate) fn encode<T: Serialize + ?Sized>(self, value: &T) -> Result<Vec<u8>, BoxError> {
+        match self {
+            #[cfg(feature = "json")]
+            Codec::Json => Ok(serde_json::to_vec(value)?),
+            #[cfg(feature = "msgpack")]
+            Codec::MessagePack => Ok(rmp_serde::to_vec_named(value)?),
+            #[cfg(feature = "bincode")]
+            Codec::Bincode => Ok(bincode::serialize(value)?),
+        }
+    }
+
+    pub(crate) fn decode<T: DeserializeOwned>(self, data: &[u8]) -> Result<T, BoxError> {
+        match self {
+            #[cfg(feature = "json")]
+            Codec::Json => Ok(serde_json::from_slice(data)?),
+            #[cfg(feature = "msgpack")]
+            Codec::MessagePack => Ok(rmp_serde::from_slice(data)?),
+            #[cfg(feature = "bincode")]
+            Codec::Bincode => Ok(bincode::deserialize(data)?),
+        }
+    }
+}
+
+/// An error sending or receiving a value with `Socket::send_serde` or
+/// `Socket::recv_serde`.
+#[derive(Debug)]
+pub enum SerdeError {
+    /// Sending or receiving the message failed.
+    Zmq(Error),
+    /// The value could not be serialized.
+    Encode(BoxError),
+    /// The received message could not be deserialized. The message is
+    /// kept, e.g. for passing it on to a dead-letter queue.
+    Decode { frame: Message, error: BoxError },
+}
+
+impl From<Error> for SerdeError {
+    fn from(error: Error) -> Self {
+        SerdeError::Zmq(error)
+    }
+}
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SerdeError::Zmq(ref e) => write!(f, "{}", e),
+            SerdeError::Encode(ref e) => write!(f, "Failed to serialize message: {}", e),
+            SerdeError::Decode { ref error, .. } => {
+                write!(f, "Failed to deserialize message: {}", error)
+            }
+        }
+    }
+}
+
+impl error::Error for SerdeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            SerdeError::Zmq(ref e) => Some(e),
+            SerdeError::Encode(ref e) | SerdeError::Decode { error: ref e, .. } => Some(&**e),
+        }
+    }
+}
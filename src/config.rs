@@ -0,0 +1,400 @@
+//! Declarative socket configuration.
+
+use std::error;
+use std::fmt;
+use std::result;
+use std::time::Duration;
+
+use crate::{Context, Error, Result, Security, Socket, SocketType};
+
+/// The configuration of a socket, applied with
+/// `Context::socket_from_config`.
+///
+/// Options that are `None` are left at their defaults. The time options
+/// hold the value passed to the corresponding `_duration` setter of
+/// `Socket`, such as `Socket::set_linger_duration`, where `None` stands
+/// for the special value of the option, like an infinite timeout.
+///
+/// With the `serde` feature enabled, the configuration can be
+/// deserialized, for example from TOML:
+///
+/// ```toml
+/// type = "SUB"
+/// rcvhwm = 1000
+/// linger = 0
+/// subscribe = ["weather.", "news."]
+/// connect = ["tcp://127.0.0.1:5556"]
+///
+/// [security]
+/// mechanism = "plain_client"
+/// username = "admin"
+/// password = "secret"
+/// ```
+///
+/// The socket type is given by the `type` key, and the `security` table
+/// is tagged by the `mechanism` key, which names the `Security` variant
+/// in snake case. CURVE keys are given in their Z85-encoded form, and the
+/// routing id and subscriptions as strings. Time options are given in
+/// milliseconds, with a negative number standing for `None`. For options
+/// whose special value is 0 in libzmq, such as `heartbeat_ivl`, 0 stands
+/// for `None` as well.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct SocketConfig {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub socket_type: SocketType,
+    pub sndhwm: Option<i32>,
+    pub rcvhwm: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_duration")
+    )]
+    pub sndtimeo: Option<Option<Duration>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_duration")
+    )]
+    pub rcvtimeo: Option<Option<Duration>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_duration")
+    )]
+    pub linger: Option<Option<Duration>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_duration")
+    )]
+    pub reconnect_ivl: Option<Option<Duration>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_nonzero_duration")
+    )]
+    pub reconnect_ivl_max: Option<Option<Duration>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_nonzero_duration")
+    )]
+    pub connect_timeout: Option<Option<Duration>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_nonzero_duration")
+    )]
+    pub handshake_ivl: Option<Option<Duration>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_nonzero_duration")
+    )]
+    pub heartbeat_ivl: Option<Option<Duration>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_nonzero_duration")
+    )]
+    pub heartbeat_ttl: Option<Option<Duration>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_duration")
+    )]
+    pub heartbeat_timeout: Option<Option<Duration>>,
+    pub security: Option<Security>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "de::optional_bytes")
+    )]
+    pub routing_id: Option<Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "de::bytes_list"))]
+    pub subscribe: Vec<Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bind: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub connect: Vec<String>,
+}
+
+impl SocketConfig {
+    /// Create a configuration for a socket of the given type, leaving
+    /// all options at their defaults.
+    pub fn new(socket_type: SocketType) -> SocketConfig {
+        SocketConfig {
+            socket_type,
+            sndhwm: None,
+            rcvhwm: None,
+            sndtimeo: None,
+            rcvtimeo: None,
+            linger: None,
+            reconnect_ivl: None,
+            reconnect_ivl_max: None,
+            connect_timeout: None,
+            handshake_ivl: None,
+            heartbeat_ivl: None,
+            heartbeat_ttl: None,
+            heartbeat_timeout: None,
+            security: None,
+            routing_id: None,
+            subscribe: Vec::new(),
+            bind: Vec::new(),
+            connect: Vec::new(),
+        }
+    }
+
+    pub(crate) fn build(&self, ctx: &Context) -> result::Result<Socket, ConfigError> {
+        let socket = ctx
+            .socket(self.socket_type)
+            .map_err(|e| ConfigError::new("type", e))?;
+        // On failure, the socket is dropped, and thereby closed, here.
+        self.apply(&socket)?;
+        Ok(socket)
+    }
+
+    fn apply(&self, socket: &Socket) -> result::Result<(), ConfigError> {
+        set_option(socket, "sndhwm", &self.sndhwm, Socket::set_sndhwm)?;
+        set_option(socket, "rcvhwm", &self.rcvhwm, Socket::set_rcvhwm)?;
+        set_option(
+            socket,
+            "sndtimeo",
+            &self.sndtimeo,
+            Socket::set_sndtimeo_duration,
+        )?;
+        set_option(
+            socket,
+            "rcvtimeo",
+            &self.rcvtimeo,
+            Socket::set_rcvtimeo_duration,
+        )?;
+        set_option(socket, "linger", &self.linger, Socket::set_linger_duration)?;
+        set_option(
+            socket,
+            "reconnect_ivl",
+            &self.reconnect_ivl,
+            Socket::set_reconnect_ivl_duration,
+        )?;
+        set_option(
+            socket,
+            "reconnect_ivl_max",
+            &self.reconnect_ivl_max,
+            Socket::set_reconnect_ivl_max_duration,
+        )?;
+        set_option(
+            socket,
+            "connect_timeout",
+            &self.connect_timeout,
+            Socket::set_connect_timeout_duration,
+        )?;
+        set_option(
+            socket,
+            "handshake_ivl",
+            &self.handshake_ivl,
+            Socket::set_handshake_ivl_duration,
+        )?;
+        set_option(
+            socket,
+            "heartbeat_ivl",
+            &self.heartbeat_ivl,
+            Socket::set_heartbeat_ivl_duration,
+        )?;
+        set_option(
+            socket,
+            "heartbeat_ttl",
+            &self.heartbeat_ttl,
+            Socket::set_heartbeat_ttl_duration,
+        )?;
+        set_option(
+            socket,
+            "heartbeat_timeout",
+            &self.heartbeat_timeout,
+            Socket::set_heartbeat_timeout_duration,
+        )?;
+        set_option(socket, "security", &self.security, Socket::set_security)?;
+        if let Some(ref routing_id) = self.routing_id {
+            socket
+                .set_identity(routing_id)
+                .map_err(|e| ConfigError::new("routing_id", e))?;
+        }
+        for (i, topic) in self.subscribe.iter().enumerate() {
+            socket
+                .set_subscribe(topic)
+                .map_err(|e| ConfigError::new(format!("subscribe[{}]", i), e))?;
+        }
+        let result = self.bind_and_connect(socket);
+        if result.is_err() {
+            // Closing the socket only releases its endpoints
+            // asynchronously, so unbind them explicitly to make them
+            // available again right away.
+            let _ = socket.disconnect_all();
+        }
+        result
+    }
+
+    fn bind_and_connect(&self, socket: &Socket) -> result::Result<(), ConfigError> {
+        for (i, endpoint) in self.bind.iter().enumerate() {
+            socket
+                .bind(endpoint)
+                .map_err(|e| ConfigError::new(format!("bind[{}]", i), e))?;
+        }
+        for (i, endpoint) in self.connect.iter().enumerate() {
+            socket
+                .connect(endpoint)
+                .map_err(|e| ConfigError::new(format!("connect[{}]", i), e))?;
+        }
+        Ok(())
+    }
+}
+
+fn set_option<T: Clone>(
+    socket: &Socket,
+    field: &'static str,
+    value: &Option<T>,
+    setter: fn(&Socket, T) -> Result<()>,
+) -> result::Result<(), ConfigError> {
+    match *value {
+        Some(ref value) => setter(socket, value.clone()).map_err(|e| ConfigError::new(field, e)),
+        None => Ok(()),
+    }
+}
+
+/// An error applying a `SocketConfig`, naming the offending field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigError {
+    field: String,
+    error: Error,
+}
+
+impl ConfigError {
+    fn new<S: Into<String>>(field: S, error: Error) -> ConfigError {
+        ConfigError {
+            field: field.into(),
+            error,
+        }
+    }
+
+    /// Return the name of the field that could not be applied.
+    ///
+    /// This is the key used for deserialization; list entries are
+    /// indexed, e.g. `bind[1]` for the second bind endpoint.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Return the error returned by 0MQ.
+    pub fn error(&self) -> Error {
+        self.error
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid socket configuration `{}`: {}",
+            self.field, self.error
+        )
+    }
+}
+
+impl error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod de {
+    //! Deserialization helpers for byte strings and CURVE keys.
+
+    use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+    use std::fmt;
+    use std::time::Duration;
+
+    use crate::z85;
+
+    /// A byte string, given either as a string or a sequence of bytes.
+    struct Bytes(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for Bytes {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+            struct BytesVisitor;
+
+            impl<'de> Visitor<'de> for BytesVisitor {
+                type Value = Bytes;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a string or a sequence of bytes")
+                }
+
+                fn visit_str<E: de::Error>(self, value: &str) -> Result<Bytes, E> {
+                    Ok(Bytes(value.as_bytes().to_vec()))
+                }
+
+                fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Bytes, E> {
+                    Ok(Bytes(value.to_vec()))
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Bytes, A::Error> {
+                    let mut bytes = Vec::new();
+                    while let Some(byte) = seq.next_element()? {
+                        bytes.push(byte);
+                    }
+                    Ok(Bytes(bytes))
+                }
+            }
+
+            deserializer.deserialize_any(BytesVisitor)
+        }
+    }
+
+    pub fn optional_bytes<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        let bytes: Option<Bytes> = Deserialize::deserialize(deserializer)?;
+        Ok(bytes.map(|bytes| bytes.0))
+    }
+
+    /// Deserialize a time option given in milliseconds, where a
+    /// negative number stands for `None`.
+    pub fn optional_duration<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Option<Duration>>, D::Error> {
+        let ms = i64::deserialize(deserializer)?;
+        if ms < 0 {
+            Ok(Some(None))
+        } else {
+            Ok(Some(Some(Duration::from_millis(ms as u64))))
+        }
+    }
+
+    /// Deserialize a time option given in milliseconds, where 0 or a
+    /// negative number stands for `None`.
+    pub fn optional_nonzero_duration<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Option<Duration>>, D::Error> {
+        let duration = optional_duration(deserializer)?;
+        Ok(duration.map(|duration| duration.filter(|d| *d != Duration::from_millis(0))))
+    }
+
+    pub fn bytes_list<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        let list: Vec<Bytes> = Deserialize::deserialize(deserializer)?;
+        Ok(list.into_iter().map(|bytes| bytes.0).collect())
+    }
+
+    /// Deserialize a Z85-encoded CURVE key.
+    pub fn z85_key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let mut key = [0; 32];
+        match z85::decode_into(&encoded, &mut key) {
+            Ok(32) => Ok(key),
+            _ => Err(de::Error::invalid_value(
+                de::Unexpected::Str(&encoded),
+                &"a Z85-encoded 32-byte key",
+            )),
+        }
+    }
+
+    /// Deserialize a Z85-encoded CURVE key into a vector.
+    pub fn z85_key_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        z85_key(deserializer).map(|key| key.to_vec())
+    }
+}
@@ -0,0 +1,493 @@
+//! Typed transport endpoints.
+
+use std::borrow::Cow;
+use std::error;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+
+use crate::SocketEndpoint;
+
+/// A 0MQ endpoint, consisting of a transport and an address.
+///
+/// Endpoints can be parsed from, and formatted as, the textual form used
+/// by libzmq:
+///
+/// ```
+/// let endpoint: zmq::Endpoint = "tcp://[::1]:5555".parse().unwrap();
+/// assert_eq!(
+///     endpoint,
+///     zmq::Endpoint::tcp(std::net::Ipv6Addr::LOCALHOST, 5555)
+/// );
+/// assert_eq!(endpoint.to_string(), "tcp://[::1]:5555");
+/// ```
+///
+/// Parsing checks the syntax of the address only; whether it can be
+/// bound or connected to is up to libzmq.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// `tcp://[source;]host:port`
+    Tcp {
+        /// The local address to connect from, if any.
+        source: Option<TcpAddr>,
+        addr: TcpAddr,
+    },
+    /// `ipc://path`; abstract socket names start with `@`.
+    Ipc(String),
+    /// `inproc://name`
+    Inproc(String),
+    /// `pgm://interface;group:port`
+    Pgm(PgmAddr),
+    /// `epgm://interface;group:port`
+    Epgm(PgmAddr),
+    /// `tipc://address`, where the address is kept as given.
+    Tipc(String),
+    /// `vmci://cid:port`, where `None` stands for the `*` wildcard.
+    Vmci { cid: Option<u32>, port: Option<u32> },
+    /// `ws://host:port[/path]`
+    Ws { addr: TcpAddr, path: String },
+    /// `wss://host:port[/path]`
+    Wss { addr: TcpAddr, path: String },
+}
+
+impl Endpoint {
+    /// Create a `tcp://` endpoint.
+    pub fn tcp<H: Into<Host>, P: Into<Port>>(host: H, port: P) -> Endpoint {
+        Endpoint::Tcp {
+            source: None,
+            addr: TcpAddr::new(host, port),
+        }
+    }
+
+    /// Create a `tcp://` endpoint connecting from the given local
+    /// address.
+    pub fn tcp_with_source(source: TcpAddr, addr: TcpAddr) -> Endpoint {
+        Endpoint::Tcp {
+            source: Some(source),
+            addr,
+        }
+    }
+
+    /// Create an `ipc://` endpoint for a filesystem path.
+    pub fn ipc<S: Into<String>>(path: S) -> Endpoint {
+        Endpoint::Ipc(path.into())
+    }
+
+    /// Create an `ipc://` endpoint for a name in the Linux abstract
+    /// socket namespace.
+    pub fn ipc_abstract(name: &str) -> Endpoint {
+        Endpoint::Ipc(format!("@{}", name))
+    }
+
+    /// Create an `inproc://` endpoint.
+    pub fn inproc<S: Into<String>>(name: S) -> Endpoint {
+        Endpoint::Inproc(name.into())
+    }
+
+    /// Return the name of the transport, e.g. `"tcp"`.
+    pub fn transport(&self) -> &'static str {
+        match *self {
+            Endpoint::Tcp { .. } => "tcp",
+            Endpoint::Ipc(_) => "ipc",
+            Endpoint::Inproc(_) => "inproc",
+            Endpoint::Pgm(_) => "pgm",
+            Endpoint::Epgm(_) => "epgm",
+            Endpoint::Tipc(_) => "tipc",
+            Endpoint::Vmci { .. } => "vmci",
+            Endpoint::Ws { .. } => "ws",
+            Endpoint::Wss { .. } => "wss",
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}://", self.transport())?;
+        match *self {
+            Endpoint::Tcp {
+                source: Some(ref source),
+                ref addr,
+            } => write!(f, "{};{}", source, addr),
+            Endpoint::Tcp {
+                source: None,
+                ref addr,
+            } => write!(f, "{}", addr),
+            Endpoint::Ipc(ref name) | Endpoint::Inproc(ref name) | Endpoint::Tipc(ref name) => {
+                f.write_str(name)
+            }
+            Endpoint::Pgm(ref addr) | Endpoint::Epgm(ref addr) => write!(f, "{}", addr),
+            Endpoint::Vmci { cid, port } => {
+                write_wildcard(f, cid)?;
+                f.write_str(":")?;
+                write_wildcard(f, port)
+            }
+            Endpoint::Ws { ref addr, ref path } | Endpoint::Wss { ref addr, ref path } => {
+                write!(f, "{}{}", addr, path)
+            }
+        }
+    }
+}
+
+fn write_wildcard(f: &mut fmt::Formatter, value: Option<u32>) -> fmt::Result {
+    match value {
+        Some(value) => write!(f, "{}", value),
+        None => f.write_str("*"),
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = EndpointError;
+
+    fn from_str(s: &str) -> Result<Endpoint, EndpointError> {
+        if s.contains('\0') {
+            return Err(EndpointError::NulByte);
+        }
+        let sep = s.find("://").ok_or(EndpointError::MissingTransport)?;
+        let (transport, address) = (&s[..sep], &s[sep + 3..]);
+        if address.is_empty() {
+            return Err(EndpointError::EmptyAddress);
+        }
+        match transport {
+            "tcp" => match address.find(';') {
+                Some(i) => Ok(Endpoint::tcp_with_source(
+                    address[..i].parse()?,
+                    address[i + 1..].parse()?,
+                )),
+                None => Ok(Endpoint::Tcp {
+                    source: None,
+                    addr: address.parse()?,
+                }),
+            },
+            "ipc" => {
+                if address == "@" {
+                    return Err(EndpointError::EmptyAddress);
+                }
+                Ok(Endpoint::Ipc(address.to_owned()))
+            }
+            "inproc" => Ok(Endpoint::Inproc(address.to_owned())),
+            "pgm" => Ok(Endpoint::Pgm(address.parse()?)),
+            "epgm" => Ok(Endpoint::Epgm(address.parse()?)),
+            "tipc" => Ok(Endpoint::Tipc(address.to_owned())),
+            "vmci" => {
+                let (cid, port) = split_port(address)?;
+                let cid =
+                    parse_wildcard(cid).map_err(|_| EndpointError::InvalidHost(cid.into()))?;
+                let port =
+                    parse_wildcard(port).map_err(|_| EndpointError::InvalidPort(port.into()))?;
+                Ok(Endpoint::Vmci { cid, port })
+            }
+            "ws" | "wss" => {
+                // Neither the host nor the port can contain a slash, so
+                // the path starts at the first one.
+                let (addr, path) = match address.find('/') {
+                    Some(i) => (&address[..i], &address[i..]),
+                    None => (address, ""),
+                };
+                let addr = addr.parse()?;
+                let path = path.to_owned();
+                if transport == "ws" {
+                    Ok(Endpoint::Ws { addr, path })
+                } else {
+                    Ok(Endpoint::Wss { addr, path })
+                }
+            }
+            _ => Err(EndpointError::UnknownTransport(transport.to_owned())),
+        }
+    }
+}
+
+fn parse_wildcard(s: &str) -> Result<Option<u32>, ()> {
+    if s == "*" {
+        Ok(None)
+    } else {
+        s.parse().map(Some).map_err(|_| ())
+    }
+}
+
+/// Split an address at the colon separating the port.
+fn split_port(s: &str) -> Result<(&str, &str), EndpointError> {
+    let i = s.rfind(':').ok_or(EndpointError::MissingPort)?;
+    if s[..i].is_empty() {
+        return Err(EndpointError::InvalidHost(String::new()));
+    }
+    Ok((&s[..i], &s[i + 1..]))
+}
+
+impl From<SocketAddr> for Endpoint {
+    fn from(addr: SocketAddr) -> Endpoint {
+        Endpoint::Tcp {
+            source: None,
+            addr: addr.into(),
+        }
+    }
+}
+
+/// A host and port of a `tcp://`, `ws://` or `wss://` endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TcpAddr {
+    pub host: Host,
+    pub port: Port,
+}
+
+impl TcpAddr {
+    pub fn new<H: Into<Host>, P: Into<Port>>(host: H, port: P) -> TcpAddr {
+        TcpAddr {
+            host: host.into(),
+            port: port.into(),
+        }
+    }
+}
+
+impl fmt::Display for TcpAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+impl FromStr for TcpAddr {
+    type Err = EndpointError;
+
+    fn from_str(s: &str) -> Result<TcpAddr, EndpointError> {
+        let (host, port) = split_port(s)?;
+        Ok(TcpAddr {
+            host: host.parse()?,
+            port: port.parse()?,
+        })
+    }
+}
+
+impl From<SocketAddr> for TcpAddr {
+    fn from(addr: SocketAddr) -> TcpAddr {
+        TcpAddr::new(addr.ip(), addr.port())
+    }
+}
+
+/// The host part of a TCP address.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Host {
+    /// `*`, i.e. all interfaces when binding.
+    Wildcard,
+    /// An IP address; IPv6 addresses are written in brackets.
+    Ip(IpAddr),
+    /// A host or network interface name.
+    Name(String),
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Host::Wildcard => f.write_str("*"),
+            Host::Ip(IpAddr::V4(ref ip)) => write!(f, "{}", ip),
+            Host::Ip(IpAddr::V6(ref ip)) => write!(f, "[{}]", ip),
+            Host::Name(ref name) => f.write_str(name),
+        }
+    }
+}
+
+impl FromStr for Host {
+    type Err = EndpointError;
+
+    fn from_str(s: &str) -> Result<Host, EndpointError> {
+        let invalid = || EndpointError::InvalidHost(s.to_owned());
+        if s == "*" {
+            Ok(Host::Wildcard)
+        } else if s.starts_with('[') && s.ends_with(']') {
+            let ip: Ipv6Addr = s[1..s.len() - 1].parse().map_err(|_| invalid())?;
+            Ok(Host::Ip(ip.into()))
+        } else if let Ok(ip) = s.parse::<Ipv4Addr>() {
+            Ok(Host::Ip(ip.into()))
+        } else if !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '.' || c == '_')
+        {
+            Ok(Host::Name(s.to_owned()))
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl From<IpAddr> for Host {
+    fn from(ip: IpAddr) -> Host {
+        Host::Ip(ip)
+    }
+}
+
+impl From<Ipv4Addr> for Host {
+    fn from(ip: Ipv4Addr) -> Host {
+        Host::Ip(ip.into())
+    }
+}
+
+impl From<Ipv6Addr> for Host {
+    fn from(ip: Ipv6Addr) -> Host {
+        Host::Ip(ip.into())
+    }
+}
+
+impl<'a> From<&'a str> for Host {
+    /// Convert `"*"` to `Host::Wildcard`, IP addresses to `Host::Ip`,
+    /// and anything else to `Host::Name`.
+    fn from(s: &'a str) -> Host {
+        match s.parse() {
+            Ok(host) => host,
+            Err(_) => Host::Name(s.to_owned()),
+        }
+    }
+}
+
+/// The port of a TCP address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Port {
+    /// `*`, i.e. an ephemeral port chosen by the operating system when
+    /// binding.
+    Ephemeral,
+    Number(u16),
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Port::Ephemeral => f.write_str("*"),
+            Port::Number(port) => write!(f, "{}", port),
+        }
+    }
+}
+
+impl FromStr for Port {
+    type Err = EndpointError;
+
+    fn from_str(s: &str) -> Result<Port, EndpointError> {
+        if s == "*" {
+            Ok(Port::Ephemeral)
+        } else {
+            s.parse()
+                .map(Port::Number)
+                .map_err(|_| EndpointError::InvalidPort(s.to_owned()))
+        }
+    }
+}
+
+impl From<u16> for Port {
+    fn from(port: u16) -> Port {
+        Port::Number(port)
+    }
+}
+
+/// The address of a `pgm://` or `epgm://` endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PgmAddr {
+    /// The name or IP address of the network interface to use.
+    pub interface: String,
+    /// The multicast group address.
+    pub group: IpAddr,
+    pub port: u16,
+}
+
+impl fmt::Display for PgmAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{};{}:{}", self.interface, self.group, self.port)
+    }
+}
+
+impl FromStr for PgmAddr {
+    type Err = EndpointError;
+
+    fn from_str(s: &str) -> Result<PgmAddr, EndpointError> {
+        let i = s
+            .find(';')
+            .ok_or_else(|| EndpointError::InvalidHost(s.to_owned()))?;
+        let (interface, group) = (&s[..i], &s[i + 1..]);
+        if interface.is_empty() {
+            return Err(EndpointError::InvalidHost(interface.to_owned()));
+        }
+        let (group, port) = split_port(group)?;
+        let group = match group.parse::<IpAddr>() {
+            Ok(ip) if ip.is_multicast() => ip,
+            _ => return Err(EndpointError::InvalidHost(group.to_owned())),
+        };
+        let port = port
+            .parse()
+            .map_err(|_| EndpointError::InvalidPort(port.to_owned()))?;
+        Ok(PgmAddr {
+            interface: interface.to_owned(),
+            group,
+            port,
+        })
+    }
+}
+
+/// An error parsing an `Endpoint`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EndpointError {
+    /// The endpoint lacks the `transport://` prefix.
+    MissingTransport,
+    UnknownTransport(String),
+    EmptyAddress,
+    MissingPort,
+    InvalidHost(String),
+    InvalidPort(String),
+    /// The endpoint contains a NUL byte.
+    NulByte,
+}
+
+impl fmt::Display for EndpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EndpointError::MissingTransport => write!(f, "Missing transport."),
+            EndpointError::UnknownTransport(ref t) => write!(f, "Unknown transport `{}`.", t),
+            EndpointError::EmptyAddress => write!(f, "Empty address."),
+            EndpointError::MissingPort => write!(f, "Missing port."),
+            EndpointError::InvalidHost(ref host) => write!(f, "Invalid host `{}`.", host),
+            EndpointError::InvalidPort(ref port) => write!(f, "Invalid port `{}`.", port),
+            EndpointError::NulByte => write!(f, "Endpoint contains a NUL byte."),
+        }
+    }
+}
+
+impl error::Error for EndpointError {}
+
+/// Types that can be passed as an endpoint to `Socket::bind`,
+/// `Socket::connect` and related methods.
+///
+/// Strings are passed to libzmq unchanged.
+pub trait AsEndpoint {
+    /// Return the endpoint in its textual form.
+    fn as_endpoint_str(&self) -> Cow<'_, str>;
+}
+
+impl AsEndpoint for str {
+    fn as_endpoint_str(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl AsEndpoint for String {
+    fn as_endpoint_str(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl AsEndpoint for Endpoint {
+    fn as_endpoint_str(&self) -> Cow<'_, str> {
+        Cow::Owned(self.to_string())
+    }
+}
+
+impl AsEndpoint for SocketAddr {
+    fn as_endpoint_str(&self) -> Cow<'_, str> {
+        Cow::Owned(Endpoint::from(*self).to_string())
+    }
+}
+
+impl AsEndpoint for SocketEndpoint {
+    fn as_endpoint_str(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.as_str())
+    }
+}
+
+impl<T: AsEndpoint + ?Sized> AsEndpoint for &T {
+    fn as_endpoint_str(&self) -> Cow<'_, str> {
+        (**self).as_endpoint_str()
+    }
+}
@@ -0,0 +1,243 @@
+//! Encoding and decoding of values as the frames of multipart messages.
+
+use std::error;
+use std::fmt;
+use std::mem;
+use std::str::{self, Utf8Error};
+
+use crate::Message;
+
+/// Types that can be encoded as the frames of a multipart message, as
+/// sent by `Socket::send_typed`.
+///
+/// Byte strings, strings and integers are encoded as a single frame,
+/// integers in network byte order. Tuples encode each of their elements
+/// in turn.
+pub trait ToFrames {
+    /// Append the frames encoding this value to `frames`.
+    fn to_frames(&self, frames: &mut Vec<Message>);
+
+    /// Encode this value as a vector of frames.
+    fn encode(&self) -> Vec<Message> {
+        let mut frames = Vec::new();
+        self.to_frames(&mut frames);
+        frames
+    }
+}
+
+/// Types that can be decoded from the frames of a multipart message, as
+/// received by `Socket::recv_typed`.
+///
+/// This is the counterpart of `ToFrames`.
+pub trait FromFrames: Sized {
+    /// Decode a value from the frames at the front of `frames`, taking
+    /// as many as it needs.
+    fn from_frames<I>(frames: &mut I) -> Result<Self, FrameError>
+    where
+        I: Iterator<Item = Message>;
+
+    /// Decode a value from all of `frames`, failing with
+    /// `FrameError::TrailingFrames` if some of them are left over.
+    fn decode(frames: Vec<Message>) -> Result<Self, FrameError> {
+        let mut frames = frames.into_iter();
+        let value = Self::from_frames(&mut frames)?;
+        match frames.len() {
+            0 => Ok(value),
+            n => Err(FrameError::TrailingFrames(n)),
+        }
+    }
+}
+
+/// An empty frame, as used for delimiters in request-reply envelopes.
+///
+/// Decoding it fails with `FrameError::NotEmpty` if the frame has any
+/// content.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Empty;
+
+/// An error decoding a multipart message with `FromFrames`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// The message has fewer frames than expected.
+    MissingFrame,
+    /// The message has more frames than expected; holds the number of
+    /// frames left over.
+    TrailingFrames(usize),
+    /// A frame does not have the length required by its type, e.g. four
+    /// bytes for a `u32`.
+    InvalidLength { expected: usize, actual: usize },
+    /// A frame decoded as a string is not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// A frame decoded as `Empty` is not empty.
+    NotEmpty,
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FrameError::MissingFrame => write!(f, "Missing frame."),
+            FrameError::TrailingFrames(n) => write!(f, "{} unexpected trailing frame(s).", n),
+            FrameError::InvalidLength { expected, actual } => {
+                write!(f, "Invalid frame length {}, expected {}.", actual, expected)
+            }
+            FrameError::InvalidUtf8(ref e) => write!(f, "Invalid UTF-8 in frame: {}", e),
+            FrameError::NotEmpty => write!(f, "Expected an empty frame."),
+        }
+    }
+}
+
+impl error::Error for FrameError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            FrameError::InvalidUtf8(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn next_frame<I>(frames: &mut I) -> Result<Message, FrameError>
+where
+    I: Iterator<Item = Message>,
+{
+    frames.next().ok_or(FrameError::MissingFrame)
+}
+
+impl<T: ToFrames + ?Sized> ToFrames for &T {
+    fn to_frames(&self, frames: &mut Vec<Message>) {
+        (**self).to_frames(frames)
+    }
+}
+
+impl ToFrames for Empty {
+    fn to_frames(&self, frames: &mut Vec<Message>) {
+        frames.push(Message::new());
+    }
+}
+
+impl FromFrames for Empty {
+    fn from_frames<I>(frames: &mut I) -> Result<Self, FrameError>
+    where
+        I: Iterator<Item = Message>,
+    {
+        if next_frame(frames)?.is_empty() {
+            Ok(Empty)
+        } else {
+            Err(FrameError::NotEmpty)
+        }
+    }
+}
+
+impl FromFrames for Message {
+    fn from_frames<I>(frames: &mut I) -> Result<Self, FrameError>
+    where
+        I: Iterator<Item = Message>,
+    {
+        next_frame(frames)
+    }
+}
+
+impl ToFrames for [u8] {
+    fn to_frames(&self, frames: &mut Vec<Message>) {
+        frames.push(Message::from(self));
+    }
+}
+
+impl ToFrames for Vec<u8> {
+    fn to_frames(&self, frames: &mut Vec<Message>) {
+        frames.push(Message::from(&self[..]));
+    }
+}
+
+impl FromFrames for Vec<u8> {
+    fn from_frames<I>(frames: &mut I) -> Result<Self, FrameError>
+    where
+        I: Iterator<Item = Message>,
+    {
+        next_frame(frames).map(|frame| frame.to_vec())
+    }
+}
+
+impl ToFrames for str {
+    fn to_frames(&self, frames: &mut Vec<Message>) {
+        frames.push(Message::from(self));
+    }
+}
+
+impl ToFrames for String {
+    fn to_frames(&self, frames: &mut Vec<Message>) {
+        frames.push(Message::from(self.as_str()));
+    }
+}
+
+impl FromFrames for String {
+    fn from_frames<I>(frames: &mut I) -> Result<Self, FrameError>
+    where
+        I: Iterator<Item = Message>,
+    {
+        let frame = next_frame(frames)?;
+        match str::from_utf8(&frame) {
+            Ok(s) => Ok(s.to_owned()),
+            Err(e) => Err(FrameError::InvalidUtf8(e)),
+        }
+    }
+}
+
+macro_rules! int_frames {
+    ($($ty:ty),*) => {$(
+        impl ToFrames for $ty {
+            fn to_frames(&self, frames: &mut Vec<Message>) {
+                frames.push(Message::from(&self.to_be_bytes()[..]));
+            }
+        }
+
+        impl FromFrames for $ty {
+            fn from_frames<I>(frames: &mut I) -> Result<Self, FrameError>
+            where
+                I: Iterator<Item = Message>,
+            {
+                let frame = next_frame(frames)?;
+                let mut bytes = [0; mem::size_of::<$ty>()];
+                if frame.len() != bytes.len() {
+                    return Err(FrameError::InvalidLength {
+                        expected: bytes.len(),
+                        actual: frame.len(),
+                    });
+                }
+                bytes.copy_from_slice(&frame);
+                Ok(<$ty>::from_be_bytes(bytes))
+            }
+        }
+    )*};
+}
+
+int_frames!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+macro_rules! tuple_frames {
+    ($($name:ident)+) => {
+        impl<$($name: ToFrames),+> ToFrames for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn to_frames(&self, frames: &mut Vec<Message>) {
+                let ($(ref $name,)+) = *self;
+                $($name.to_frames(frames);)+
+            }
+        }
+
+        impl<$($name: FromFrames),+> FromFrames for ($($name,)+) {
+            fn from_frames<I>(frames: &mut I) -> Result<Self, FrameError>
+            where
+                I: Iterator<Item = Message>,
+            {
+                Ok(($($name::from_frames(frames)?,)+))
+            }
+        }
+    };
+}
+
+tuple_frames!(A);
+tuple_frames!(A B);
+tuple_frames!(A B C);
+tuple_frames!(A B C D);
+tuple_frames!(A B C D E);
+tuple_frames!(A B C D E F);
+tuple_frames!(A B C D E F G);
+tuple_frames!(A B C D E F G H);
@@ -5,13 +5,19 @@
 use bitflags::bitflags;
 use libc::{c_int, c_long, c_short};
 
+use std::cell::RefCell;
+use std::cmp;
 use std::ffi;
 use std::fmt;
 use std::marker::PhantomData;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::{Deref, DerefMut, RangeInclusive};
 use std::os::raw::c_void;
 use std::result;
 use std::string::FromUtf8Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use std::{mem, ptr, str};
 
 use zmq_sys::{errno, RawFd};
@@ -26,11 +32,25 @@ macro_rules! zmq_try {
     }}
 }
 
+#[cfg(any(feature = "json", feature = "msgpack", feature = "bincode"))]
+mod codec;
+mod config;
+mod endpoint;
+mod frames;
 mod message;
+mod pool;
 mod sockopt;
+pub mod z85;
 
+#[cfg(any(feature = "json", feature = "msgpack", feature = "bincode"))]
+pub use crate::codec::{Codec, SerdeError};
+pub use crate::config::{ConfigError, SocketConfig};
+pub use crate::endpoint::{AsEndpoint, Endpoint, EndpointError, Host, PgmAddr, Port, TcpAddr};
+pub use crate::frames::{Empty, FrameError, FromFrames, ToFrames};
 use crate::message::msg_ptr;
-pub use crate::message::Message;
+pub use crate::message::{Message, MessageFlag};
+pub use crate::pool::{MessagePool, PoolStats};
+pub use crate::z85::{DecodeError, EncodeError};
 pub use crate::SocketType::*;
 
 /// `zmq`-specific Result type.
@@ -39,6 +59,7 @@ pub type Result<T> = result::Result<T, Error>;
 /// Socket types
 #[allow(non_camel_case_types)]
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub enum SocketType {
     PAIR,
     PUB,
@@ -72,8 +93,11 @@ impl SocketType {
         };
         raw as c_int
     }
-    fn from_raw(raw: c_int) -> SocketType {
-        match raw as u32 {
+
+    /// Decode a raw `ZMQ_*` socket type, returning `None` for types
+    /// not known to these bindings, such as the draft socket types.
+    pub fn try_from_raw(raw: i32) -> Option<SocketType> {
+        let socket_type = match raw as u32 {
             zmq_sys::ZMQ_PAIR => PAIR,
             zmq_sys::ZMQ_PUB => PUB,
             zmq_sys::ZMQ_SUB => SUB,
@@ -86,8 +110,31 @@ impl SocketType {
             zmq_sys::ZMQ_XPUB => XPUB,
             zmq_sys::ZMQ_XSUB => XSUB,
             zmq_sys::ZMQ_STREAM => STREAM,
-            _ => panic!("socket type is out of range!"),
-        }
+            _ => return None,
+        };
+        Some(socket_type)
+    }
+
+    /// Decode a socket type from its name, as exchanged in the ZMTP
+    /// handshake and reported by the `Socket-Type` message property,
+    /// returning `None` for types not known to these bindings.
+    pub fn try_from_name(name: &str) -> Option<SocketType> {
+        let socket_type = match name {
+            "PAIR" => PAIR,
+            "PUB" => PUB,
+            "SUB" => SUB,
+            "REQ" => REQ,
+            "REP" => REP,
+            "DEALER" => DEALER,
+            "ROUTER" => ROUTER,
+            "PULL" => PULL,
+            "PUSH" => PUSH,
+            "XPUB" => XPUB,
+            "XSUB" => XSUB,
+            "STREAM" => STREAM,
+            _ => return None,
+        };
+        Some(socket_type)
     }
 }
 
@@ -119,10 +166,22 @@ impl SocketEvent {
         self as u16
     }
 
-    // TODO: this should not need to be public
+    /// Decode a raw `ZMQ_EVENT_*` value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event is not known to these bindings.
+    #[deprecated(since = "0.10.0", note = "Use `try_from_raw` instead")]
     pub fn from_raw(raw: u16) -> SocketEvent {
+        SocketEvent::try_from_raw(raw).expect("unknown event type")
+    }
+
+    /// Decode a raw `ZMQ_EVENT_*` value, returning `None` for events
+    /// not known to these bindings, e.g. ones added in newer libzmq
+    /// versions.
+    pub fn try_from_raw(raw: u16) -> Option<SocketEvent> {
         use SocketEvent::*;
-        match u32::from(raw) {
+        let event = match u32::from(raw) {
             zmq_sys::ZMQ_EVENT_CONNECTED => CONNECTED,
             zmq_sys::ZMQ_EVENT_CONNECT_DELAYED => CONNECT_DELAYED,
             zmq_sys::ZMQ_EVENT_CONNECT_RETRIED => CONNECT_RETRIED,
@@ -139,17 +198,189 @@ impl SocketEvent {
             zmq_sys::ZMQ_EVENT_HANDSHAKE_FAILED_PROTOCOL => HANDSHAKE_FAILED_PROTOCOL,
             zmq_sys::ZMQ_EVENT_HANDSHAKE_FAILED_AUTH => HANDSHAKE_FAILED_AUTH,
             zmq_sys::ZMQ_EVENT_ALL => ALL,
-            x => panic!("unknown event type {}", x),
+            _ => return None,
+        };
+        Some(event)
+    }
+}
+
+/// The reason for a failed security handshake.
+///
+/// This is decoded from the value accompanying the `HANDSHAKE_FAILED_*`
+/// socket events delivered to a monitor socket; see
+/// `HandshakeFailure::from_event` and `Socket::connect_and_handshake`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandshakeFailure {
+    /// The handshake failed without a more specific reason; the
+    /// `errno` value reported by 0MQ is given.
+    NoDetail(i32),
+    /// The ZAP handler rejected the peer, with the given ZAP status
+    /// code: 300 for a temporary error, 400 if authentication was
+    /// denied, and 500 for an internal error.
+    Auth(u32),
+    ZmtpUnspecified,
+    ZmtpUnexpectedCommand,
+    ZmtpInvalidSequence,
+    ZmtpKeyExchange,
+    ZmtpMalformedCommandUnspecified,
+    ZmtpMalformedCommandMessage,
+    ZmtpMalformedCommandHello,
+    ZmtpMalformedCommandInitiate,
+    ZmtpMalformedCommandError,
+    ZmtpMalformedCommandReady,
+    ZmtpMalformedCommandWelcome,
+    ZmtpInvalidMetadata,
+    /// A cryptographic error, e.g. due to a peer using the wrong key.
+    ZmtpCryptographic,
+    /// The peers are configured for different security mechanisms.
+    ZmtpMechanismMismatch,
+    ZapUnspecified,
+    ZapMalformedReply,
+    ZapBadRequestId,
+    ZapBadVersion,
+    ZapInvalidStatusCode,
+    ZapInvalidMetadata,
+    /// A protocol error code unknown to these bindings.
+    Protocol(u32),
+}
+
+impl HandshakeFailure {
+    /// Decode the value of a monitor event.
+    ///
+    /// Returns `None` if `event` does not indicate a handshake failure.
+    pub fn from_event(event: SocketEvent, value: u32) -> Option<HandshakeFailure> {
+        use HandshakeFailure::*;
+        match event {
+            SocketEvent::HANDSHAKE_FAILED_NO_DETAIL => Some(NoDetail(value as i32)),
+            SocketEvent::HANDSHAKE_FAILED_AUTH => Some(Auth(value)),
+            SocketEvent::HANDSHAKE_FAILED_PROTOCOL => Some(match value {
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_UNSPECIFIED => ZmtpUnspecified,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_UNEXPECTED_COMMAND => ZmtpUnexpectedCommand,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_INVALID_SEQUENCE => ZmtpInvalidSequence,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_KEY_EXCHANGE => ZmtpKeyExchange,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_UNSPECIFIED => {
+                    ZmtpMalformedCommandUnspecified
+                }
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_MESSAGE => {
+                    ZmtpMalformedCommandMessage
+                }
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_HELLO => {
+                    ZmtpMalformedCommandHello
+                }
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_INITIATE => {
+                    ZmtpMalformedCommandInitiate
+                }
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_ERROR => {
+                    ZmtpMalformedCommandError
+                }
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_READY => {
+                    ZmtpMalformedCommandReady
+                }
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_MALFORMED_COMMAND_WELCOME => {
+                    ZmtpMalformedCommandWelcome
+                }
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_INVALID_METADATA => ZmtpInvalidMetadata,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_CRYPTOGRAPHIC => ZmtpCryptographic,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_MECHANISM_MISMATCH => ZmtpMechanismMismatch,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZAP_UNSPECIFIED => ZapUnspecified,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZAP_MALFORMED_REPLY => ZapMalformedReply,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZAP_BAD_REQUEST_ID => ZapBadRequestId,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZAP_BAD_VERSION => ZapBadVersion,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZAP_INVALID_STATUS_CODE => ZapInvalidStatusCode,
+                zmq_sys::ZMQ_PROTOCOL_ERROR_ZAP_INVALID_METADATA => ZapInvalidMetadata,
+                code => Protocol(code),
+            }),
+            _ => None,
         }
     }
 }
 
+impl fmt::Display for HandshakeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use HandshakeFailure::*;
+        let description = match *self {
+            NoDetail(errno) => {
+                let s = unsafe { ffi::CStr::from_ptr(zmq_sys::zmq_strerror(errno)) };
+                return write!(f, "handshake failed: {}", s.to_string_lossy());
+            }
+            Auth(status_code) => {
+                return write!(f, "authentication failed with ZAP status {}", status_code)
+            }
+            Protocol(code) => return write!(f, "protocol error 0x{:x}", code),
+            ZmtpUnspecified => "unspecified ZMTP protocol error",
+            ZmtpUnexpectedCommand => "unexpected ZMTP command",
+            ZmtpInvalidSequence => "invalid ZMTP command sequence",
+            ZmtpKeyExchange => "ZMTP key exchange failed",
+            ZmtpMalformedCommandUnspecified => "malformed ZMTP command",
+            ZmtpMalformedCommandMessage => "malformed ZMTP MESSAGE command",
+            ZmtpMalformedCommandHello => "malformed ZMTP HELLO command",
+            ZmtpMalformedCommandInitiate => "malformed ZMTP INITIATE command",
+            ZmtpMalformedCommandError => "malformed ZMTP ERROR command",
+            ZmtpMalformedCommandReady => "malformed ZMTP READY command",
+            ZmtpMalformedCommandWelcome => "malformed ZMTP WELCOME command",
+            ZmtpInvalidMetadata => "invalid ZMTP metadata",
+            ZmtpCryptographic => "cryptographic error",
+            ZmtpMechanismMismatch => "security mechanism mismatch",
+            ZapUnspecified => "unspecified ZAP error",
+            ZapMalformedReply => "malformed ZAP reply",
+            ZapBadRequestId => "bad ZAP request id",
+            ZapBadVersion => "bad ZAP version",
+            ZapInvalidStatusCode => "invalid ZAP status code",
+            ZapInvalidMetadata => "invalid ZAP metadata",
+        };
+        f.write_str(description)
+    }
+}
+
+impl std::error::Error for HandshakeFailure {}
+
 /// Flag for socket `send` methods that specifies non-blocking mode.
 pub static DONTWAIT: i32 = zmq_sys::ZMQ_DONTWAIT as i32;
 /// Flag for socket `send` methods that specifies that more frames of a
 /// multipart message will follow.
 pub static SNDMORE: i32 = zmq_sys::ZMQ_SNDMORE as i32;
 
+bitflags! {
+    /// Flags for the socket `send` methods.
+    ///
+    /// The methods accept anything convertible into `SendFlags`, which
+    /// includes the `i32` values of `DONTWAIT` and `SNDMORE`, and
+    /// combinations thereof, as well as `0`.
+    pub struct SendFlags: i32 {
+        /// Send in non-blocking mode, failing with `EAGAIN` if the
+        /// message cannot be queued.
+        const DONTWAIT = zmq_sys::ZMQ_DONTWAIT as i32;
+        /// More frames of a multipart message will follow.
+        const SNDMORE = zmq_sys::ZMQ_SNDMORE as i32;
+    }
+}
+
+bitflags! {
+    /// Flags for the socket `recv` methods.
+    ///
+    /// The methods accept anything convertible into `RecvFlags`, which
+    /// includes the `i32` value of `DONTWAIT`, as well as `0`.
+    pub struct RecvFlags: i32 {
+        /// Receive in non-blocking mode, failing with `EAGAIN` if no
+        /// message is available.
+        const DONTWAIT = zmq_sys::ZMQ_DONTWAIT as i32;
+    }
+}
+
+impl From<i32> for SendFlags {
+    /// Convert raw flags, ignoring bits that do not apply to sending.
+    fn from(flags: i32) -> SendFlags {
+        SendFlags::from_bits_truncate(flags)
+    }
+}
+
+impl From<i32> for RecvFlags {
+    /// Convert raw flags, ignoring bits that do not apply to receiving.
+    fn from(flags: i32) -> RecvFlags {
+        RecvFlags::from_bits_truncate(flags)
+    }
+}
+
 /// Security Mechanism
 #[allow(non_camel_case_types)]
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -161,6 +392,21 @@ pub enum Mechanism {
     ZMQ_GSSAPI,
 }
 
+impl Mechanism {
+    /// Decode a raw `ZMQ_*` mechanism, returning `None` for mechanisms
+    /// not known to these bindings.
+    pub fn try_from_raw(raw: i32) -> Option<Mechanism> {
+        let mechanism = match raw as u32 {
+            zmq_sys::ZMQ_NULL => Mechanism::ZMQ_NULL,
+            zmq_sys::ZMQ_PLAIN => Mechanism::ZMQ_PLAIN,
+            zmq_sys::ZMQ_CURVE => Mechanism::ZMQ_CURVE,
+            zmq_sys::ZMQ_GSSAPI => Mechanism::ZMQ_GSSAPI,
+            _ => return None,
+        };
+        Some(mechanism)
+    }
+}
+
 /// An error returned by a 0MQ API function.
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub enum Error {
@@ -194,6 +440,10 @@ pub enum Error {
     ENOCOMPATPROTO,
     ETERM,
     EMTHREAD,
+
+    /// An error code not covered by the other variants, e.g. one only
+    /// used by newer libzmq versions or the operating system.
+    Other(i32),
 }
 
 impl Error {
@@ -228,6 +478,8 @@ impl Error {
             Error::ENOCOMPATPROTO => errno::ENOCOMPATPROTO,
             Error::ETERM => errno::ETERM,
             Error::EMTHREAD => errno::EMTHREAD,
+
+            Error::Other(raw) => raw,
         }
     }
 
@@ -270,18 +522,11 @@ impl Error {
             errno::EINPROGRESS_ALT => Error::EINPROGRESS,
             errno::ENOTSOCK_ALT => Error::ENOTSOCK,
             errno::EMSGSIZE_ALT => Error::EMSGSIZE,
+            errno::ENOTCONN_ALT => Error::ENOTCONN,
+            errno::EHOSTUNREACH_ALT => Error::EHOSTUNREACH,
 
-            // TODO: these are present in `zmq-sys`, but not handled, as that
-            // would break backwards-compatibility for the `Error` enum.
-
-            // errno::EAFNOSUPPORT_ALT => Error::EAFNOSUPPORT,
-            // errno::ENETUNREACH_ALT => Error::ENETUNREACH,
-            // errno::ECONNABORTED_ALT => Error::ECONNABORTED,
-            // errno::ECONNRESET_ALT => Error::ECONNRESET,
-            // errno::ENOTCONN_ALT => Error::ENOTCONN,
-            // errno::ETIMEDOUT_ALT => Error::ETIMEDOUT,
-            // errno::EHOSTUNREACH_ALT => Error::EHOSTUNREACH,
-            // errno::ENETRESET_ALT => Error::ENETRESET,
+            // The remaining alternative codes, e.g. `ETIMEDOUT_ALT`, have
+            // no variant of their own, and are kept as `Error::Other`.
 
             // 0MQ native error codes
             errno::EFSM => Error::EFSM,
@@ -289,14 +534,32 @@ impl Error {
             errno::ETERM => Error::ETERM,
             errno::EMTHREAD => Error::EMTHREAD,
 
-            x => unsafe {
-                let s = zmq_sys::zmq_strerror(x);
-                panic!(
-                    "unknown error [{}]: {}",
-                    x,
-                    str::from_utf8(ffi::CStr::from_ptr(s).to_bytes()).unwrap()
-                )
-            },
+            x => Error::Other(x),
+        }
+    }
+
+    /// Attach the operation that failed, e.g. for logging.
+    ///
+    /// For endpoints, `Socket::bind_with_operation` and its siblings do
+    /// this already.
+    ///
+    /// ```
+    /// let ctx = zmq::Context::new();
+    /// let socket = ctx.socket(zmq::PULL).unwrap();
+    /// let endpoint = "bogus://endpoint";
+    /// let error = socket
+    ///     .connect(endpoint)
+    ///     .map_err(|e| e.with_operation(zmq::Operation::Connect(endpoint.into())))
+    ///     .unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "failed to connect to bogus://endpoint: Protocol not supported"
+    /// );
+    /// ```
+    pub fn with_operation(self, operation: Operation) -> OperationError {
+        OperationError {
+            error: self,
+            operation,
         }
     }
 
@@ -343,6 +606,15 @@ impl From<Error> for std::io::Error {
             Error::EAGAIN => ErrorKind::WouldBlock,
             Error::EINVAL => ErrorKind::InvalidInput,
             Error::EINTR => ErrorKind::Interrupted,
+            Error::Other(errno::ETIMEDOUT_ALT) => ErrorKind::TimedOut,
+            Error::Other(errno::ECONNRESET_ALT) => ErrorKind::ConnectionReset,
+            Error::Other(errno::ECONNABORTED_ALT) => ErrorKind::ConnectionAborted,
+            // Other error codes below the 0MQ range are native errno
+            // values, e.g. `ETIMEDOUT`, which the standard library knows
+            // how to classify.
+            Error::Other(raw) if raw < errno::ZMQ_HAUSNUMERO => {
+                std::io::Error::from_raw_os_error(raw).kind()
+            }
             _ => ErrorKind::Other,
         };
         // TODO: With rust 1.14 and up there is an optimization
@@ -353,6 +625,85 @@ impl From<Error> for std::io::Error {
     }
 }
 
+/// A 0MQ error, together with the operation that failed.
+///
+/// This is created with `Error::with_operation`, or returned by
+/// `Socket::bind_with_operation` and its siblings, and is useful where
+/// errors end up in logs, to see which endpoint or option is
+/// concerned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OperationError {
+    error: Error,
+    operation: Operation,
+}
+
+impl OperationError {
+    /// Return the error returned by 0MQ.
+    pub fn error(&self) -> Error {
+        self.error
+    }
+
+    /// Return the operation that failed.
+    pub fn operation(&self) -> &Operation {
+        &self.operation
+    }
+}
+
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to {}: {}", self.operation, self.error)
+    }
+}
+
+impl std::error::Error for OperationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<OperationError> for Error {
+    fn from(error: OperationError) -> Self {
+        error.error
+    }
+}
+
+impl From<OperationError> for std::io::Error {
+    fn from(error: OperationError) -> Self {
+        let kind = std::io::Error::from(error.error).kind();
+        std::io::Error::new(kind, error)
+    }
+}
+
+/// An operation on a socket, as carried by `OperationError`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Bind(String),
+    Unbind(String),
+    Connect(String),
+    Disconnect(String),
+    /// Setting the option of the given name, e.g. `"ZMQ_SNDHWM"`.
+    SetSockOpt(&'static str),
+    /// Getting the option of the given name.
+    GetSockOpt(&'static str),
+    Send,
+    Recv,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Operation::Bind(ref endpoint) => write!(f, "bind to {}", endpoint),
+            Operation::Unbind(ref endpoint) => write!(f, "unbind from {}", endpoint),
+            Operation::Connect(ref endpoint) => write!(f, "connect to {}", endpoint),
+            Operation::Disconnect(ref endpoint) => write!(f, "disconnect from {}", endpoint),
+            Operation::SetSockOpt(name) => write!(f, "set socket option {}", name),
+            Operation::GetSockOpt(name) => write!(f, "get socket option {}", name),
+            Operation::Send => f.write_str("send"),
+            Operation::Recv => f.write_str("receive"),
+        }
+    }
+}
+
 fn errno_to_error() -> Error {
     Error::from_raw(unsafe { zmq_sys::zmq_errno() })
 }
@@ -444,9 +795,20 @@ impl Context {
             sock,
             context: Some(self.clone()),
             owned: true,
+            endpoints: RefCell::new(Vec::new()),
         })
     }
 
+    /// Create a socket configured according to `config`.
+    ///
+    /// The settings are applied in the order of the fields of
+    /// `SocketConfig`, so the endpoints are bound and connected last. If
+    /// any of them fails, the socket is closed again, and the returned
+    /// error names the offending field.
+    pub fn socket_from_config(&self, config: &SocketConfig) -> result::Result<Socket, ConfigError> {
+        config.build(self)
+    }
+
     /// Try to destroy the context. This is different than the destructor; the
     /// destructor will loop when zmq_ctx_term returns EINTR.
     pub fn destroy(&mut self) -> Result<()> {
@@ -468,6 +830,7 @@ pub struct Socket {
     #[allow(dead_code)]
     context: Option<Context>,
     owned: bool,
+    endpoints: RefCell<Vec<SocketEndpoint>>,
 }
 
 unsafe impl Send for Socket {}
@@ -544,6 +907,26 @@ macro_rules! sockopts {
     };
 }
 
+/// Declare `Option<Duration>` accessors for time options measured in
+/// milliseconds; `$none` is the raw value that `None` stands for.
+macro_rules! duration_sockopts {
+    ( $( $(#[$meta:meta])* ($getter:ident, $setter:ident) => $constant_name:ident, none = $none:expr; )* ) => {
+        $(
+            $(#[$meta])*
+            pub fn $getter(&self) -> Result<Option<Duration>> {
+                let ms: i32 = sockopt::get(self.sock, zmq_sys::$constant_name as c_int)?;
+                Ok(sockopt::duration_from_ms(ms, $none))
+            }
+
+            $(#[$meta])*
+            pub fn $setter(&self, value: Option<Duration>) -> Result<()> {
+                let ms = sockopt::duration_to_ms(value, $none)?;
+                sockopt::set(self.sock, zmq_sys::$constant_name as c_int, ms)
+            }
+        )*
+    };
+}
+
 /// Sendable over a `Socket`.
 ///
 /// A type can implement this trait there is an especially efficient
@@ -595,6 +978,7 @@ impl Socket {
             sock,
             context: None,
             owned: true,
+            endpoints: RefCell::new(Vec::new()),
         }
     }
 
@@ -608,67 +992,329 @@ impl Socket {
     }
 
     /// Accept connections on a socket.
-    pub fn bind(&self, endpoint: &str) -> Result<()> {
-        let c_str = ffi::CString::new(endpoint.as_bytes()).unwrap();
+    ///
+    /// On success, the endpoint is recorded in `endpoints`, in its
+    /// resolved form as returned by `get_last_endpoint`.
+    pub fn bind<E>(&self, endpoint: E) -> Result<()>
+    where
+        E: AsEndpoint,
+    {
+        let endpoint = endpoint.as_endpoint_str();
+        let c_str = endpoint_c_str(&endpoint)?;
         zmq_try!(unsafe { zmq_sys::zmq_bind(self.sock, c_str.as_ptr()) });
+        let resolved = match self.get_last_endpoint() {
+            Ok(Ok(resolved)) => resolved,
+            _ => endpoint.into_owned(),
+        };
+        self.endpoints
+            .borrow_mut()
+            .push(SocketEndpoint::Bound(resolved));
+        Ok(())
+    }
+
+    /// Stop accepting connections on a previously bound endpoint.
+    ///
+    /// For endpoints bound with a wildcard address or port, the
+    /// resolved endpoint, as returned by `get_last_endpoint` or
+    /// `endpoints`, needs to be given.
+    pub fn unbind<E>(&self, endpoint: E) -> Result<()>
+    where
+        E: AsEndpoint,
+    {
+        let endpoint = endpoint.as_endpoint_str();
+        let c_str = endpoint_c_str(&endpoint)?;
+        zmq_try!(unsafe { zmq_sys::zmq_unbind(self.sock, c_str.as_ptr()) });
+        self.forget_endpoint(&SocketEndpoint::Bound(endpoint.into_owned()));
         Ok(())
     }
 
+    /// Bind to an ephemeral TCP port on the given host, returning the
+    /// address bound to.
+    ///
+    /// This binds to `tcp://host:*`. If `host` is the wildcard `"*"`,
+    /// the socket listens on all interfaces, and the loopback address is
+    /// returned in place of the unspecified address reported by
+    /// `get_last_endpoint`, so that the result can be passed to
+    /// `connect` on the same machine.
+    pub fn bind_ephemeral<H>(&self, host: H) -> Result<SocketAddr>
+    where
+        H: Into<Host>,
+    {
+        self.bind(Endpoint::tcp(host, Port::Ephemeral))?;
+        self.last_tcp_addr()
+    }
+
+    /// Bind to the first available TCP port in `ports` on the given
+    /// host, returning the address bound to.
+    ///
+    /// Ports already in use are skipped; if none is available,
+    /// `Error::EADDRINUSE` is returned. The returned address is
+    /// determined as for `bind_ephemeral`.
+    pub fn bind_in_range<H>(&self, host: H, ports: RangeInclusive<u16>) -> Result<SocketAddr>
+    where
+        H: Into<Host>,
+    {
+        let host = host.into();
+        if ports.start() > ports.end() {
+            return Err(Error::EINVAL);
+        }
+        for port in ports {
+            match self.bind(Endpoint::tcp(host.clone(), port)) {
+                Ok(()) => return self.last_tcp_addr(),
+                Err(Error::EADDRINUSE) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Err(Error::EADDRINUSE)
+    }
+
+    /// Return the address of the last TCP endpoint bound to, with the
+    /// unspecified address replaced by the loopback address.
+    fn last_tcp_addr(&self) -> Result<SocketAddr> {
+        let endpoint = self.get_last_endpoint()?.map_err(|_| Error::EINVAL)?;
+        match endpoint.parse() {
+            Ok(Endpoint::Tcp {
+                addr:
+                    TcpAddr {
+                        host: Host::Ip(ip),
+                        port: Port::Number(port),
+                    },
+                ..
+            }) => {
+                let ip = match ip {
+                    IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                    ip => ip,
+                };
+                Ok(SocketAddr::new(ip, port))
+            }
+            _ => Err(Error::EINVAL),
+        }
+    }
+
     /// Connect a socket.
-    pub fn connect(&self, endpoint: &str) -> Result<()> {
-        let c_str = ffi::CString::new(endpoint.as_bytes()).unwrap();
+    ///
+    /// On success, the endpoint is recorded in `endpoints`.
+    pub fn connect<E>(&self, endpoint: E) -> Result<()>
+    where
+        E: AsEndpoint,
+    {
+        let endpoint = endpoint.as_endpoint_str();
+        let c_str = endpoint_c_str(&endpoint)?;
         zmq_try!(unsafe { zmq_sys::zmq_connect(self.sock, c_str.as_ptr()) });
+        self.endpoints
+            .borrow_mut()
+            .push(SocketEndpoint::Connected(endpoint.into_owned()));
         Ok(())
     }
 
     /// Disconnect a previously connected socket
-    pub fn disconnect(&self, endpoint: &str) -> Result<()> {
-        let c_str = ffi::CString::new(endpoint.as_bytes()).unwrap();
+    pub fn disconnect<E>(&self, endpoint: E) -> Result<()>
+    where
+        E: AsEndpoint,
+    {
+        let endpoint = endpoint.as_endpoint_str();
+        let c_str = endpoint_c_str(&endpoint)?;
         zmq_try!(unsafe { zmq_sys::zmq_disconnect(self.sock, c_str.as_ptr()) });
+        self.forget_endpoint(&SocketEndpoint::Connected(endpoint.into_owned()));
         Ok(())
     }
 
+    /// Like `bind`, but on failure, the returned error names the
+    /// endpoint.
+    pub fn bind_with_operation<E>(&self, endpoint: E) -> result::Result<(), OperationError>
+    where
+        E: AsEndpoint,
+    {
+        let endpoint = endpoint.as_endpoint_str();
+        self.bind(&*endpoint)
+            .map_err(|e| e.with_operation(Operation::Bind(endpoint.into_owned())))
+    }
+
+    /// Like `unbind`, but on failure, the returned error names the
+    /// endpoint.
+    pub fn unbind_with_operation<E>(&self, endpoint: E) -> result::Result<(), OperationError>
+    where
+        E: AsEndpoint,
+    {
+        let endpoint = endpoint.as_endpoint_str();
+        self.unbind(&*endpoint)
+            .map_err(|e| e.with_operation(Operation::Unbind(endpoint.into_owned())))
+    }
+
+    /// Like `connect`, but on failure, the returned error names the
+    /// endpoint.
+    pub fn connect_with_operation<E>(&self, endpoint: E) -> result::Result<(), OperationError>
+    where
+        E: AsEndpoint,
+    {
+        let endpoint = endpoint.as_endpoint_str();
+        self.connect(&*endpoint)
+            .map_err(|e| e.with_operation(Operation::Connect(endpoint.into_owned())))
+    }
+
+    /// Like `disconnect`, but on failure, the returned error names the
+    /// endpoint.
+    pub fn disconnect_with_operation<E>(&self, endpoint: E) -> result::Result<(), OperationError>
+    where
+        E: AsEndpoint,
+    {
+        let endpoint = endpoint.as_endpoint_str();
+        self.disconnect(&*endpoint)
+            .map_err(|e| e.with_operation(Operation::Disconnect(endpoint.into_owned())))
+    }
+
+    /// Return the endpoints the socket is bound and connected to, in
+    /// the order they were added.
+    ///
+    /// This covers the endpoints added by `bind` and `connect` on this
+    /// `Socket`, and not yet removed with `unbind` and `disconnect`.
+    /// The endpoints of a socket obtained by `from_raw` are not known.
+    pub fn endpoints(&self) -> Vec<SocketEndpoint> {
+        self.endpoints.borrow().clone()
+    }
+
+    /// Unbind and disconnect all endpoints returned by `endpoints`.
+    ///
+    /// All endpoints are attempted, even if some of them fail; those
+    /// are kept in `endpoints`, and the first error is returned.
+    pub fn disconnect_all(&self) -> Result<()> {
+        let mut result = Ok(());
+        for endpoint in self.endpoints() {
+            let detached = match endpoint {
+                SocketEndpoint::Bound(ref endpoint) => self.unbind(endpoint),
+                SocketEndpoint::Connected(ref endpoint) => self.disconnect(endpoint),
+            };
+            if result.is_ok() {
+                result = detached;
+            }
+        }
+        result
+    }
+
+    // Like `zmq_disconnect` and `zmq_unbind`, this removes all matching
+    // endpoints, as a socket may be connected to the same one repeatedly.
+    fn forget_endpoint(&self, endpoint: &SocketEndpoint) {
+        self.endpoints.borrow_mut().retain(|e| e != endpoint);
+    }
+
     /// Configure the socket for monitoring
     pub fn monitor(&self, monitor_endpoint: &str, events: i32) -> Result<()> {
-        let c_str = ffi::CString::new(monitor_endpoint.as_bytes()).unwrap();
+        let c_str = endpoint_c_str(monitor_endpoint)?;
         zmq_try!(unsafe {
             zmq_sys::zmq_socket_monitor(self.sock, c_str.as_ptr(), events as c_int)
         });
         Ok(())
     }
 
+    /// Connect a socket, and wait for the security handshake with the
+    /// peer to finish.
+    ///
+    /// This is a diagnostic aid for misconfigured security mechanisms,
+    /// which otherwise only show up as connections that never deliver
+    /// any messages. The result is `Ok(Ok(()))` if the handshake
+    /// succeeded, and `Ok(Err(failure))` with the reason if it failed.
+    /// If no handshake finishes within `timeout`, `Err(Error::EAGAIN)` is
+    /// returned. In both error cases, the endpoint is disconnected again.
+    ///
+    /// Note that some failures, for example a client using the wrong
+    /// server key with CURVE, are only detected by the server, so the
+    /// client will run into the timeout.
+    ///
+    /// This temporarily sets up a monitor on the socket, and disables
+    /// monitoring again when done. Hence, a monitor previously configured
+    /// with `monitor` is removed, and needs to be set up again if
+    /// further events are of interest. It requires libzmq 4.3 or
+    /// newer, which reports handshake events, and a socket created
+    /// from a `Context`; for sockets obtained with `from_raw`, it fails
+    /// with `EINVAL`.
+    pub fn connect_and_handshake<E>(
+        &self,
+        endpoint: E,
+        timeout: Duration,
+    ) -> Result<result::Result<(), HandshakeFailure>>
+    where
+        E: AsEndpoint,
+    {
+        static MONITOR_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let ctx = match self.context {
+            Some(ref ctx) => ctx,
+            None => return Err(Error::EINVAL),
+        };
+        let monitor_endpoint = format!(
+            "inproc://zmq-rs-handshake-monitor-{}",
+            MONITOR_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let events = zmq_sys::ZMQ_EVENT_HANDSHAKE_SUCCEEDED
+            | zmq_sys::ZMQ_EVENT_HANDSHAKE_FAILED_NO_DETAIL
+            | zmq_sys::ZMQ_EVENT_HANDSHAKE_FAILED_PROTOCOL
+            | zmq_sys::ZMQ_EVENT_HANDSHAKE_FAILED_AUTH;
+        self.monitor(&monitor_endpoint, events as i32)?;
+
+        let result = ctx.socket(PAIR).and_then(|monitor| {
+            monitor.connect(&monitor_endpoint)?;
+            self.connect(&endpoint)?;
+            if monitor.poll_duration(POLLIN, Some(timeout))? == 0 {
+                return Err(Error::EAGAIN);
+            }
+            let (event, value) = recv_monitor_event(&monitor)?;
+            let event = SocketEvent::try_from_raw(event).ok_or(Error::EINVAL)?;
+            match HandshakeFailure::from_event(event, value) {
+                Some(failure) => Ok(Err(failure)),
+                None => Ok(Ok(())),
+            }
+        });
+
+        let rc = unsafe { zmq_sys::zmq_socket_monitor(self.sock, ptr::null(), 0) };
+        let result = match result {
+            Ok(_) if rc == -1 => Err(errno_to_error()),
+            result => result,
+        };
+        match result {
+            Ok(Ok(())) => {}
+            _ => {
+                let _ = self.disconnect(&endpoint);
+            }
+        }
+        result
+    }
+
     /// Send a message.
     ///
     /// Due to the provided `From` implementations, this works for
     /// `&[u8]`, `Vec<u8>` and `&str` `Message` itself.
-    pub fn send<T>(&self, data: T, flags: i32) -> Result<()>
+    pub fn send<T, F>(&self, data: T, flags: F) -> Result<()>
     where
         T: Sendable,
+        F: Into<SendFlags>,
     {
-        data.send(self, flags)
+        data.send(self, flags.into().bits())
     }
 
     /// Send a `Message` message.
     #[deprecated(since = "0.9.0", note = "Use `send` instead")]
-    pub fn send_msg(&self, msg: Message, flags: i32) -> Result<()> {
+    pub fn send_msg<F: Into<SendFlags>>(&self, msg: Message, flags: F) -> Result<()> {
         self.send(msg, flags)
     }
 
     #[deprecated(since = "0.9.0", note = "Use `send` instead")]
-    pub fn send_str(&self, data: &str, flags: i32) -> Result<()> {
+    pub fn send_str<F: Into<SendFlags>>(&self, data: &str, flags: F) -> Result<()> {
         self.send(data, flags)
     }
 
-    pub fn send_multipart<I, T>(&self, iter: I, flags: i32) -> Result<()>
+    pub fn send_multipart<I, T, F>(&self, iter: I, flags: F) -> Result<()>
     where
         I: IntoIterator<Item = T>,
         T: Into<Message>,
+        F: Into<SendFlags>,
     {
+        let flags = flags.into();
         let mut last_part: Option<T> = None;
         for part in iter {
             let maybe_last = last_part.take();
             if let Some(last) = maybe_last {
-                self.send(last.into(), flags | SNDMORE)?;
+                self.send(last.into(), flags | SendFlags::SNDMORE)?;
             }
             last_part = Some(part);
         }
@@ -679,32 +1325,162 @@ impl Socket {
         }
     }
 
+    /// Send a value as a multipart message, encoded by its `ToFrames`
+    /// implementation.
+    pub fn send_typed<T, F>(&self, value: &T, flags: F) -> Result<()>
+    where
+        T: ToFrames + ?Sized,
+        F: Into<SendFlags>,
+    {
+        self.send_multipart(value.encode(), flags)
+    }
+
+    /// Serialize a value with the given codec, and send it as a single
+    /// frame.
+    #[cfg(any(feature = "json", feature = "msgpack", feature = "bincode"))]
+    pub fn send_serde<T, F>(
+        &self,
+        value: &T,
+        codec: Codec,
+        flags: F,
+    ) -> result::Result<(), SerdeError>
+    where
+        T: serde::Serialize + ?Sized,
+        F: Into<SendFlags>,
+    {
+        let data = codec.encode(value).map_err(SerdeError::Encode)?;
+        self.send(data, flags)?;
+        Ok(())
+    }
+
+    /// Send static data without allocating or copying it.
+    ///
+    /// This uses `zmq_send_const`, which makes 0MQ reference `data`
+    /// directly. See also `Message::from_static`.
+    pub fn send_static<F>(&self, data: &'static [u8], flags: F) -> Result<()>
+    where
+        F: Into<SendFlags>,
+    {
+        let flags = flags.into().bits();
+        zmq_try!(unsafe {
+            zmq_sys::zmq_send_const(
+                self.sock,
+                data.as_ptr() as *const c_void,
+                data.len(),
+                flags as c_int,
+            )
+        });
+        Ok(())
+    }
+
+    /// Send the given buffers as the frames of a multipart message.
+    ///
+    /// The buffers can be of any type dereferencing to `[u8]`, such as
+    /// `&[u8]`, `Vec<u8>` or `std::io::IoSlice`. Each one is passed to
+    /// `zmq_send` in turn, without creating intermediate `Message`s or
+    /// vectors. Sending no buffers sends nothing.
+    ///
+    /// The frames are sent one at a time, so this is not atomic: if
+    /// sending a frame other than the first fails, the frames before it
+    /// have already been queued, with `SNDMORE` set, as an incomplete
+    /// multipart message. As 0MQ normally accepts the remaining frames
+    /// of a multipart message once it accepted the first one, this is
+    /// rare, e.g. when the context is terminated. The rest of the
+    /// message then has to be sent to complete it, otherwise the next
+    /// message sent is appended to it.
+    pub fn send_multipart_buffers<B, F>(&self, bufs: &[B], flags: F) -> Result<()>
+    where
+        B: Deref<Target = [u8]>,
+        F: Into<SendFlags>,
+    {
+        // `zmq_sendiov` would copy each buffer into a message all the
+        // same, and expects `struct iovec`, which zmq-sys declares as
+        // an opaque type, and `IoSlice` only matches on Unix.
+        let flags = flags.into();
+        let last = match bufs.len().checked_sub(1) {
+            Some(last) => last,
+            None => return Ok(()),
+        };
+        for (i, buf) in bufs.iter().enumerate() {
+            let flags = if i < last {
+                flags | SendFlags::SNDMORE
+            } else {
+                flags
+            };
+            zmq_try!(unsafe {
+                zmq_sys::zmq_send(
+                    self.sock,
+                    buf.as_ptr() as *const c_void,
+                    buf.len(),
+                    flags.bits() as c_int,
+                )
+            });
+        }
+        Ok(())
+    }
+
     /// Receive a message into a `Message`. The length passed to zmq_msg_recv
     /// is the length of the buffer.
-    pub fn recv(&self, msg: &mut Message, flags: i32) -> Result<()> {
+    pub fn recv<F: Into<RecvFlags>>(&self, msg: &mut Message, flags: F) -> Result<()> {
+        let flags = flags.into().bits();
         zmq_try!(unsafe { zmq_sys::zmq_msg_recv(msg_ptr(msg), self.sock, flags as c_int) });
         Ok(())
     }
 
     /// Receive bytes into a slice. The length passed to `zmq_recv` is the length of the slice. The
-    /// return value is the number of bytes in the message, which may be larger than the length of
-    /// the slice, indicating truncation.
-    pub fn recv_into(&self, bytes: &mut [u8], flags: i32) -> Result<usize> {
+    /// returned `RecvResult` holds both the number of bytes written to the slice and the length
+    /// of the message, which may be larger, indicating truncation.
+    pub fn recv_into<F: Into<RecvFlags>>(&self, bytes: &mut [u8], flags: F) -> Result<RecvResult> {
+        let flags = flags.into().bits();
         let bytes_ptr = bytes.as_mut_ptr() as *mut c_void;
         let rc = zmq_try!(unsafe {
             zmq_sys::zmq_recv(self.sock, bytes_ptr, bytes.len(), flags as c_int)
         });
-        Ok(rc as usize)
+        let full_len = rc as usize;
+        Ok(RecvResult {
+            received: cmp::min(full_len, bytes.len()),
+            full_len,
+        })
+    }
+
+    /// Receive a multipart message into the given buffers, one frame
+    /// per buffer.
+    ///
+    /// The buffers can be of any type dereferencing mutably to `[u8]`,
+    /// such as `&mut [u8]`, `Vec<u8>` or `std::io::IoSliceMut`. Each
+    /// frame is received with `recv_into` in turn, and, likewise, a
+    /// `RecvResult` is returned for each frame, reporting whether it was
+    /// truncated. Frames beyond the number of buffers are discarded, but
+    /// still included in the returned results. If receiving a frame
+    /// other than the first fails, the rest of the multipart message is
+    /// left to be received.
+    pub fn recv_multipart_buffers<B, F>(&self, bufs: &mut [B], flags: F) -> Result<Vec<RecvResult>>
+    where
+        B: DerefMut<Target = [u8]>,
+        F: Into<RecvFlags>,
+    {
+        let flags = flags.into();
+        let mut results = Vec::with_capacity(bufs.len());
+        loop {
+            let result = match bufs.get_mut(results.len()) {
+                Some(buf) => self.recv_into(buf, flags)?,
+                None => self.recv_into(&mut [], flags)?,
+            };
+            results.push(result);
+            if !self.get_rcvmore()? {
+                return Ok(results);
+            }
+        }
     }
 
     /// Receive a message into a fresh `Message`.
-    pub fn recv_msg(&self, flags: i32) -> Result<Message> {
+    pub fn recv_msg<F: Into<RecvFlags>>(&self, flags: F) -> Result<Message> {
         let mut msg = Message::new();
         self.recv(&mut msg, flags).map(|_| msg)
     }
 
     /// Receive a message as a byte vector.
-    pub fn recv_bytes(&self, flags: i32) -> Result<Vec<u8>> {
+    pub fn recv_bytes<F: Into<RecvFlags>>(&self, flags: F) -> Result<Vec<u8>> {
         self.recv_msg(flags).map(|msg| msg.to_vec())
     }
 
@@ -712,7 +1488,10 @@ impl Socket {
     ///
     /// If the received message is not valid UTF-8, it is returned as the original
     /// Vec in the `Err` part of the inner result.
-    pub fn recv_string(&self, flags: i32) -> Result<result::Result<String, Vec<u8>>> {
+    pub fn recv_string<F: Into<RecvFlags>>(
+        &self,
+        flags: F,
+    ) -> Result<result::Result<String, Vec<u8>>> {
         self.recv_bytes(flags)
             .map(|bytes| String::from_utf8(bytes).map_err(FromUtf8Error::into_bytes))
     }
@@ -721,8 +1500,10 @@ impl Socket {
     ///
     /// Note that this will allocate a new vector for each message part; for
     /// many applications it will be possible to process the different parts
-    /// sequentially and reuse allocations that way.
-    pub fn recv_multipart(&self, flags: i32) -> Result<Vec<Vec<u8>>> {
+    /// sequentially and reuse allocations that way, or to use
+    /// `recv_multipart_into`.
+    pub fn recv_multipart<F: Into<RecvFlags>>(&self, flags: F) -> Result<Vec<Vec<u8>>> {
+        let flags = flags.into();
         let mut parts: Vec<Vec<u8>> = vec![];
         loop {
             let part = self.recv_bytes(flags)?;
@@ -736,6 +1517,71 @@ impl Socket {
         Ok(parts)
     }
 
+    /// Receive a multipart message into a vector of messages.
+    ///
+    /// Each frame is received into the message at its position, appending
+    /// new messages as needed; messages left over from a longer previous
+    /// multipart message are dropped. If an error is returned, `parts` may
+    /// hold a partially received multipart message.
+    ///
+    /// Unlike `recv_multipart`, this avoids allocating a vector and
+    /// copying each frame into a `Vec<u8>`. The content of the messages
+    /// is not reused, though: 0MQ releases it when receiving into a
+    /// message, and allocates the content of each frame as usual.
+    pub fn recv_multipart_into<F: Into<RecvFlags>>(
+        &self,
+        parts: &mut Vec<Message>,
+        flags: F,
+    ) -> Result<()> {
+        let flags = flags.into();
+        let mut received = 0;
+        loop {
+            if received == parts.len() {
+                parts.push(Message::new());
+            }
+            let part = &mut parts[received];
+            self.recv(part, flags)?;
+            received += 1;
+            if !part.get_more() {
+                break;
+            }
+        }
+        parts.truncate(received);
+        Ok(())
+    }
+
+    /// Receive a multipart message, decoded by the `FromFrames`
+    /// implementation of `T`.
+    ///
+    /// The whole multipart message is received before decoding it. If it
+    /// does not match `T`, the decoding error is returned in the `Err`
+    /// part of the inner result.
+    pub fn recv_typed<T, F>(&self, flags: F) -> Result<result::Result<T, FrameError>>
+    where
+        T: FromFrames,
+        F: Into<RecvFlags>,
+    {
+        let mut frames = Vec::new();
+        self.recv_multipart_into(&mut frames, flags)?;
+        Ok(T::decode(frames))
+    }
+
+    /// Receive a single frame, and deserialize it with the given codec.
+    ///
+    /// If the frame cannot be deserialized, it is returned as part of the
+    /// `SerdeError::Decode` error.
+    #[cfg(any(feature = "json", feature = "msgpack", feature = "bincode"))]
+    pub fn recv_serde<T, F>(&self, codec: Codec, flags: F) -> result::Result<T, SerdeError>
+    where
+        T: serde::de::DeserializeOwned,
+        F: Into<RecvFlags>,
+    {
+        let frame = self.recv_msg(flags)?;
+        codec
+            .decode(&frame)
+            .map_err(|error| SerdeError::Decode { frame, error })
+    }
+
     sockopts! {
         /// Accessor for the `ZMQ_IPV6` option.
         (is_ipv6, set_ipv6) => ZMQ_IPV6 as bool,
@@ -753,11 +1599,103 @@ impl Socket {
         (is_gssapi_plaintext, set_gssapi_plaintext) => ZMQ_GSSAPI_PLAINTEXT as bool,
         (_, set_req_relaxed) => ZMQ_REQ_RELAXED as bool,
         (_, set_req_correlate) => ZMQ_REQ_CORRELATE as bool,
+        (_, set_router_raw) => ZMQ_ROUTER_RAW as bool,
+        (_, set_xpub_nodrop) => ZMQ_XPUB_NODROP as bool,
+        (_, set_xpub_manual) => ZMQ_XPUB_MANUAL as bool,
+        (_, set_xpub_verboser) => ZMQ_XPUB_VERBOSER as bool,
+        (_, set_stream_notify) => ZMQ_STREAM_NOTIFY as bool,
+        (is_invert_matching, set_invert_matching) => ZMQ_INVERT_MATCHING as bool,
+        /// Return true if the socket may be used from multiple threads
+        /// at the same time, as is the case for the draft `SERVER`,
+        /// `CLIENT`, `RADIO` and `DISH` socket types.
+        (is_thread_safe) => ZMQ_THREAD_SAFE as bool,
     }
 
     /// Return the type of this socket.
+    ///
+    /// Fails with `EINVAL` for socket types not known to these
+    /// bindings, see `SocketType::try_from_raw`.
     pub fn get_socket_type(&self) -> Result<SocketType> {
-        sockopt::get(self.sock, zmq_sys::ZMQ_TYPE as c_int).map(SocketType::from_raw)
+        self.get_raw_socket_type()
+            .and_then(|raw| SocketType::try_from_raw(raw).ok_or(Error::EINVAL))
+    }
+
+    /// Read all gettable options of the socket, e.g. for logging its
+    /// effective configuration.
+    ///
+    /// Options the linked libzmq rejects with `EINVAL`, because it does
+    /// not support them or they do not apply to the socket type, are
+    /// `None` in the result. See `SocketOptions` for the options left
+    /// out.
+    pub fn options_snapshot(&self) -> Result<SocketOptions> {
+        Ok(SocketOptions {
+            socket_type: skip_einval(self.get_raw_socket_type())?
+                .and_then(SocketType::try_from_raw),
+            last_endpoint: skip_einval(self.get_last_endpoint())?.map(lossy),
+            mechanism: skip_einval(self.get_mechanism())?,
+            routing_id: skip_einval(self.get_identity())?,
+            ipv6: skip_einval(self.is_ipv6())?,
+            immediate: skip_einval(self.is_immediate())?,
+            conflate: skip_einval(self.is_conflate())?,
+            probe_router: skip_einval(self.is_probe_router())?,
+            router_mandatory: skip_einval(self.is_router_mandatory())?,
+            router_handover: skip_einval(self.is_router_handover())?,
+            invert_matching: skip_einval(self.is_invert_matching())?,
+            thread_safe: skip_einval(self.is_thread_safe())?,
+            maxmsgsize: skip_einval(self.get_maxmsgsize())?,
+            sndhwm: skip_einval(self.get_sndhwm())?,
+            rcvhwm: skip_einval(self.get_rcvhwm())?,
+            affinity: skip_einval(self.get_affinity())?,
+            rate: skip_einval(self.get_rate())?,
+            recovery_ivl: skip_einval(self.get_recovery_ivl())?,
+            sndbuf: skip_einval(self.get_sndbuf())?,
+            rcvbuf: skip_einval(self.get_rcvbuf())?,
+            tos: skip_einval(self.get_tos())?,
+            linger: skip_einval(self.get_linger())?,
+            reconnect_ivl: skip_einval(self.get_reconnect_ivl())?,
+            reconnect_ivl_max: skip_einval(self.get_reconnect_ivl_max())?,
+            backlog: skip_einval(self.get_backlog())?,
+            multicast_hops: skip_einval(self.get_multicast_hops())?,
+            multicast_maxtpdu: skip_einval(self.get_multicast_maxtpdu())?,
+            rcvtimeo: skip_einval(self.get_rcvtimeo())?,
+            sndtimeo: skip_einval(self.get_sndtimeo())?,
+            tcp_keepalive: skip_einval(self.get_tcp_keepalive())?,
+            tcp_keepalive_cnt: skip_einval(self.get_tcp_keepalive_cnt())?,
+            tcp_keepalive_idle: skip_einval(self.get_tcp_keepalive_idle())?,
+            tcp_keepalive_intvl: skip_einval(self.get_tcp_keepalive_intvl())?,
+            tcp_maxrt: skip_einval(self.get_tcp_maxrt())?,
+            handshake_ivl: skip_einval(self.get_handshake_ivl())?,
+            heartbeat_ivl: skip_einval(self.get_heartbeat_ivl())?,
+            heartbeat_ttl: skip_einval(self.get_heartbeat_ttl())?,
+            heartbeat_timeout: skip_einval(self.get_heartbeat_timeout())?,
+            connect_timeout: skip_einval(self.get_connect_timeout())?,
+            use_fd: skip_einval(self.get_use_fd())?,
+            bindtodevice: skip_einval(self.get_bindtodevice())?.map(lossy),
+            socks_proxy: skip_einval(self.get_socks_proxy())?.map(lossy),
+            plain_server: skip_einval(self.is_plain_server())?,
+            plain_username: skip_einval(self.get_plain_username())?.map(lossy),
+            curve_server: skip_einval(self.is_curve_server())?,
+            curve_publickey: skip_einval(self.get_curve_publickey())?,
+            curve_serverkey: skip_einval(self.get_curve_serverkey())?,
+            gssapi_server: skip_einval(self.is_gssapi_server())?,
+            gssapi_plaintext: skip_einval(self.is_gssapi_plaintext())?,
+            gssapi_principal: skip_einval(self.get_gssapi_principal())?.map(lossy),
+            gssapi_service_principal: skip_einval(self.get_gssapi_service_principal())?.map(lossy),
+            gssapi_principal_nametype: skip_einval(self.get_gssapi_principal_nametype())?,
+            gssapi_service_principal_nametype: skip_einval(
+                self.get_gssapi_service_principal_nametype(),
+            )?,
+            zap_domain: skip_einval(self.get_zap_domain())?.map(lossy),
+            zap_enforce_domain: skip_einval(self.is_zap_enforce_domain())?,
+            vmci_buffer_size: skip_einval(self.get_vmci_buffer_size())?,
+            vmci_buffer_min_size: skip_einval(self.get_vmci_buffer_min_size())?,
+            vmci_buffer_max_size: skip_einval(self.get_vmci_buffer_max_size())?,
+            vmci_connect_timeout: skip_einval(self.get_vmci_connect_timeout())?,
+        })
+    }
+
+    fn get_raw_socket_type(&self) -> Result<c_int> {
+        sockopt::get(self.sock, zmq_sys::ZMQ_TYPE as c_int)
     }
 
     /// Return true if there are more frames of a multipart message to receive.
@@ -837,6 +1775,96 @@ impl Socket {
         (get_heartbeat_ttl, set_heartbeat_ttl) => ZMQ_HEARTBEAT_TTL as i32,
         (get_heartbeat_timeout, set_heartbeat_timeout) => ZMQ_HEARTBEAT_TIMEOUT as i32,
         (get_connect_timeout, set_connect_timeout) => ZMQ_CONNECT_TIMEOUT as i32,
+        (get_tcp_maxrt, set_tcp_maxrt) => ZMQ_TCP_MAXRT as i32,
+        (get_multicast_maxtpdu, set_multicast_maxtpdu) => ZMQ_MULTICAST_MAXTPDU as i32,
+        /// Accessor for the `ZMQ_USE_FD` option.
+        ///
+        /// Setting this to an existing socket file descriptor makes the
+        /// next `bind` use it, instead of creating a new one; `-1`
+        /// restores the default behavior.
+        (get_use_fd, set_use_fd) => ZMQ_USE_FD as i32,
+        /// Accessor for the `ZMQ_GSSAPI_PRINCIPAL_NAMETYPE` option.
+        ///
+        /// The value is one of the `ZMQ_GSSAPI_NT_*` constants of
+        /// `zmq_sys`.
+        (get_gssapi_principal_nametype, set_gssapi_principal_nametype) => ZMQ_GSSAPI_PRINCIPAL_NAMETYPE as i32,
+        /// Accessor for the `ZMQ_GSSAPI_SERVICE_PRINCIPAL_NAMETYPE`
+        /// option.
+        ///
+        /// The value is one of the `ZMQ_GSSAPI_NT_*` constants of
+        /// `zmq_sys`.
+        (get_gssapi_service_principal_nametype, set_gssapi_service_principal_nametype) => ZMQ_GSSAPI_SERVICE_PRINCIPAL_NAMETYPE as i32,
+        (get_vmci_buffer_size, set_vmci_buffer_size) => ZMQ_VMCI_BUFFER_SIZE as u64,
+        (get_vmci_buffer_min_size, set_vmci_buffer_min_size) => ZMQ_VMCI_BUFFER_MIN_SIZE as u64,
+        (get_vmci_buffer_max_size, set_vmci_buffer_max_size) => ZMQ_VMCI_BUFFER_MAX_SIZE as u64,
+        (get_vmci_connect_timeout, set_vmci_connect_timeout) => ZMQ_VMCI_CONNECT_TIMEOUT as i32,
+    }
+
+    duration_sockopts! {
+        /// Accessor for the `ZMQ_RCVTIMEO` option as a duration.
+        ///
+        /// `None` means receiving blocks indefinitely.
+        (get_rcvtimeo_duration, set_rcvtimeo_duration) => ZMQ_RCVTIMEO, none = -1;
+        /// Accessor for the `ZMQ_SNDTIMEO` option as a duration.
+        ///
+        /// `None` means sending blocks indefinitely.
+        (get_sndtimeo_duration, set_sndtimeo_duration) => ZMQ_SNDTIMEO, none = -1;
+        /// Accessor for the `ZMQ_LINGER` option as a duration.
+        ///
+        /// `None` means pending messages are kept indefinitely after
+        /// the socket is closed.
+        (get_linger_duration, set_linger_duration) => ZMQ_LINGER, none = -1;
+        /// Accessor for the `ZMQ_RECONNECT_IVL` option as a duration.
+        ///
+        /// `None` means no reconnection is attempted.
+        (get_reconnect_ivl_duration, set_reconnect_ivl_duration) => ZMQ_RECONNECT_IVL, none = -1;
+        /// Accessor for the `ZMQ_HANDSHAKE_IVL` option as a duration.
+        ///
+        /// `None` means the handshake never times out.
+        (get_handshake_ivl_duration, set_handshake_ivl_duration) => ZMQ_HANDSHAKE_IVL, none = 0;
+        /// Accessor for the `ZMQ_CONNECT_TIMEOUT` option as a duration.
+        ///
+        /// `None` means connecting never times out, apart from the
+        /// limits imposed by the operating system.
+        (get_connect_timeout_duration, set_connect_timeout_duration) => ZMQ_CONNECT_TIMEOUT, none = 0;
+        /// Accessor for the `ZMQ_HEARTBEAT_IVL` option as a duration.
+        ///
+        /// `None` means no heartbeats are sent.
+        (get_heartbeat_ivl_duration, set_heartbeat_ivl_duration) => ZMQ_HEARTBEAT_IVL, none = 0;
+        /// Accessor for the `ZMQ_HEARTBEAT_TTL` option as a duration.
+        ///
+        /// `None` means the peer should not time out the connection.
+        /// Note that libzmq stores this value with a resolution of 100
+        /// milliseconds.
+        (get_heartbeat_ttl_duration, set_heartbeat_ttl_duration) => ZMQ_HEARTBEAT_TTL, none = 0;
+        /// Accessor for the `ZMQ_HEARTBEAT_TIMEOUT` option as a duration.
+        ///
+        /// `None` means the heartbeat interval is used as the timeout.
+        /// This is the default, which libzmq does not allow restoring,
+        /// so setting `None` fails with `EINVAL`.
+        (get_heartbeat_timeout_duration, set_heartbeat_timeout_duration) => ZMQ_HEARTBEAT_TIMEOUT, none = -1;
+        /// Accessor for the `ZMQ_RECONNECT_IVL_MAX` option as a duration.
+        ///
+        /// `None` means the reconnection interval stays at
+        /// `ZMQ_RECONNECT_IVL`, rather than backing off exponentially.
+        (get_reconnect_ivl_max_duration, set_reconnect_ivl_max_duration) => ZMQ_RECONNECT_IVL_MAX, none = 0;
+        /// Accessor for the `ZMQ_TCP_MAXRT` option as a duration.
+        ///
+        /// `None` means the default of the operating system is used.
+        (get_tcp_maxrt_duration, set_tcp_maxrt_duration) => ZMQ_TCP_MAXRT, none = 0;
+    }
+
+    /// Get the `ZMQ_RECOVERY_IVL` option as a duration.
+    ///
+    /// Unlike the other time options, this one has no special value.
+    pub fn get_recovery_ivl_duration(&self) -> Result<Duration> {
+        let ms = self.get_recovery_ivl()?;
+        Ok(Duration::from_millis(cmp::max(ms, 0) as u64))
+    }
+
+    /// Set the `ZMQ_RECOVERY_IVL` option as a duration.
+    pub fn set_recovery_ivl_duration(&self, value: Duration) -> Result<()> {
+        self.set_recovery_ivl(sockopt::duration_to_ms(Some(value), -1)?)
     }
 
     // TODO: deprecate to align with ZMQ's preferred naming
@@ -851,14 +1879,13 @@ impl Socket {
         sockopt::get_string(self.sock, zmq_sys::ZMQ_SOCKS_PROXY as c_int, 255, true)
     }
 
+    /// Return the security mechanism of this socket.
+    ///
+    /// Fails with `EINVAL` for mechanisms not known to these bindings,
+    /// see `Mechanism::try_from_raw`.
     pub fn get_mechanism(&self) -> Result<Mechanism> {
-        sockopt::get(self.sock, zmq_sys::ZMQ_MECHANISM as c_int).map(|mech| match mech {
-            zmq_sys::ZMQ_NULL => Mechanism::ZMQ_NULL,
-            zmq_sys::ZMQ_PLAIN => Mechanism::ZMQ_PLAIN,
-            zmq_sys::ZMQ_CURVE => Mechanism::ZMQ_CURVE,
-            zmq_sys::ZMQ_GSSAPI => Mechanism::ZMQ_GSSAPI,
-            _ => panic!("Mechanism is out of range!"),
-        })
+        let raw: c_int = sockopt::get(self.sock, zmq_sys::ZMQ_MECHANISM as c_int)?;
+        Mechanism::try_from_raw(raw).ok_or(Error::EINVAL)
     }
 
     pub fn get_plain_username(&self) -> Result<result::Result<String, Vec<u8>>> {
@@ -937,11 +1964,59 @@ impl Socket {
         )
     }
 
+    /// Get the `ZMQ_BINDTODEVICE` option value.
+    ///
+    /// This is the name of the network interface that the underlying
+    /// TCP and UDP sockets are bound to, or an empty string if they are
+    /// not bound to any; see `set_bindtodevice`.
+    pub fn get_bindtodevice(&self) -> Result<result::Result<String, Vec<u8>>> {
+        // 16 = maximum interface name length (IFNAMSIZ), including the null byte
+        sockopt::get_string(self.sock, zmq_sys::ZMQ_BINDTODEVICE as c_int, 16, true)
+    }
+
+    /// Add an application metadata property, which is sent to peers
+    /// during the handshake, using the `ZMQ_METADATA` option.
+    ///
+    /// Peers can read the property from messages received from this
+    /// socket using `Message::metadata`. The property must be added
+    /// before binding or connecting. Fails with `EINVAL` unless `key`
+    /// starts with `X-`, is at most 255 bytes long and contains no `:`,
+    /// and `value` is not empty; neither may contain a NUL byte. This
+    /// option requires libzmq 4.3 built with the draft API.
+    pub fn add_metadata(&self, key: &str, value: &str) -> Result<()> {
+        if !key.starts_with("X-")
+            || key.len() > 255
+            || key.contains(':')
+            || value.is_empty()
+            || key.contains('\0')
+            || value.contains('\0')
+        {
+            return Err(Error::EINVAL);
+        }
+        let property = format!("{}:{}", key, value);
+        sockopt::set(
+            self.sock,
+            zmq_sys::ZMQ_METADATA as c_int,
+            property.as_bytes(),
+        )
+    }
+
     sockopts! {
         (_, set_socks_proxy) => ZMQ_SOCKS_PROXY as Option<&str>,
         (_, set_plain_username) => ZMQ_PLAIN_USERNAME as Option<&str>,
         (_, set_plain_password) => ZMQ_PLAIN_PASSWORD as Option<&str>,
         (_, set_zap_domain) => ZMQ_ZAP_DOMAIN as &str,
+        /// Accessor for the `ZMQ_ZAP_ENFORCE_DOMAIN` option.
+        ///
+        /// When enabled, ZAP follows RFC 27 strictly: a ZAP domain is
+        /// required for the ZAP handler to be consulted with NULL
+        /// security, and handshakes fail if no ZAP handler is
+        /// available. By default, it is disabled, keeping the lenient
+        /// legacy behaviour of accepting connections if there is no
+        /// ZAP handler. This option requires libzmq 4.3 or newer; it
+        /// was introduced as part of the draft API, so older 4.3
+        /// releases only support it when built with the draft API.
+        (is_zap_enforce_domain, set_zap_enforce_domain) => ZMQ_ZAP_ENFORCE_DOMAIN as bool,
         (_, set_xpub_welcome_msg) => ZMQ_XPUB_WELCOME_MSG as Option<&str>,
         (_, set_xpub_verbose) => ZMQ_XPUB_VERBOSE as bool,
 
@@ -950,6 +2025,113 @@ impl Socket {
         (_, set_curve_serverkey) => ZMQ_CURVE_SERVERKEY as &[u8],
         (_, set_gssapi_principal) => ZMQ_GSSAPI_PRINCIPAL as &str,
         (_, set_gssapi_service_principal) => ZMQ_GSSAPI_SERVICE_PRINCIPAL as &str,
+        (_, set_connect_routing_id) => ZMQ_CONNECT_ROUTING_ID as &[u8],
+        (_, set_bindtodevice) => ZMQ_BINDTODEVICE as &str,
+    }
+
+    /// Configure the security mechanism of the socket.
+    ///
+    /// This sets all the options required by the given configuration,
+    /// in the order libzmq expects them. The configuration is validated
+    /// before any option is set: CURVE keys must be 32 bytes long,
+    /// usernames and passwords between 1 and 255 bytes, and ZAP domains
+    /// at most 255 bytes.
+    ///
+    /// To avoid silently mixing the options of different mechanisms,
+    /// this fails with `EINVAL` if the socket already uses a mechanism,
+    /// as reported by `get_mechanism`, other than the requested one.
+    /// Apply `Security::Null` first to switch between mechanisms.
+    ///
+    /// Note that libzmq does not allow clearing the ZAP domain, so a
+    /// domain set previously is kept when none is given.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let ctx = zmq::Context::new();
+    /// let socket = ctx.socket(zmq::REQ).unwrap();
+    /// socket
+    ///     .set_security(zmq::Security::PlainClient {
+    ///         username: "admin".into(),
+    ///         password: "secret".into(),
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(socket.get_mechanism().unwrap(), zmq::Mechanism::ZMQ_PLAIN);
+    /// ```
+    pub fn set_security(&self, security: Security) -> Result<()> {
+        fn check_len(value: &[u8], min: usize, max: usize) -> Result<()> {
+            if value.len() < min || value.len() > max {
+                return Err(Error::EINVAL);
+            }
+            Ok(())
+        }
+
+        match security {
+            Security::Null => {}
+            Security::PlainClient {
+                ref username,
+                ref password,
+            } => {
+                check_len(username.as_bytes(), 1, 255)?;
+                check_len(password.as_bytes(), 1, 255)?;
+            }
+            Security::PlainServer { ref zap_domain }
+            | Security::CurveServer { ref zap_domain, .. } => {
+                if let Some(ref domain) = *zap_domain {
+                    check_len(domain.as_bytes(), 0, 255)?;
+                }
+            }
+            Security::CurveClient { ref server_key, .. } => {
+                if server_key.len() != 32 {
+                    return Err(Error::EINVAL);
+                }
+            }
+        }
+        let mechanism = security.mechanism();
+        let current = self.get_mechanism()?;
+        if mechanism != Mechanism::ZMQ_NULL
+            && current != Mechanism::ZMQ_NULL
+            && current != mechanism
+        {
+            return Err(Error::EINVAL);
+        }
+
+        match security {
+            Security::Null => {
+                // Disabling the server role resets the mechanism to NULL.
+                self.set_plain_server(false)?;
+            }
+            Security::PlainClient { username, password } => {
+                self.set_plain_username(Some(&username))?;
+                self.set_plain_password(Some(&password))?;
+            }
+            Security::PlainServer { zap_domain } => {
+                self.set_plain_server(true)?;
+                if let Some(domain) = zap_domain {
+                    self.set_zap_domain(&domain)?;
+                }
+            }
+            Security::CurveClient {
+                server_key,
+                keypair,
+            } => {
+                self.set_curve_serverkey(&server_key)?;
+                self.set_curve_publickey(&keypair.public_key)?;
+                self.set_curve_secretkey(&keypair.secret_key)?;
+            }
+            Security::CurveServer {
+                keypair,
+                zap_domain,
+            } => {
+                self.set_curve_server(true)?;
+                self.set_curve_publickey(&keypair.public_key)?;
+                self.set_curve_secretkey(&keypair.secret_key)?;
+                if let Some(domain) = zap_domain {
+                    self.set_zap_domain(&domain)?;
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Create a `PollItem` from the socket.
@@ -970,6 +2152,176 @@ impl Socket {
     pub fn poll(&self, events: PollEvents, timeout_ms: i64) -> Result<i32> {
         poll(&mut [self.as_poll_item(events)], timeout_ms)
     }
+
+    /// Like `poll`, but with the timeout given as a duration, with
+    /// `None` blocking indefinitely; see `poll_duration`.
+    pub fn poll_duration(&self, events: PollEvents, timeout: Option<Duration>) -> Result<i32> {
+        poll_duration(&mut [self.as_poll_item(events)], timeout)
+    }
+}
+
+impl fmt::Debug for Socket {
+    /// Show the socket type, the last endpoint, and key options.
+    ///
+    /// Options that cannot be read are left out; use
+    /// `Socket::options_snapshot` for the complete configuration.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("Socket");
+        if let Ok(raw) = self.get_raw_socket_type() {
+            match SocketType::try_from_raw(raw) {
+                Some(socket_type) => s.field("type", &socket_type),
+                None => s.field("type", &raw),
+            };
+        }
+        if let Ok(endpoint) = self.get_last_endpoint() {
+            s.field("last_endpoint", &lossy(endpoint));
+        }
+        s.field("endpoints", &self.endpoints.borrow());
+        if let Ok(mechanism) = self.get_mechanism() {
+            s.field("mechanism", &mechanism);
+        }
+        if let Ok(sndhwm) = self.get_sndhwm() {
+            s.field("sndhwm", &sndhwm);
+        }
+        if let Ok(rcvhwm) = self.get_rcvhwm() {
+            s.field("rcvhwm", &rcvhwm);
+        }
+        if let Ok(linger) = self.get_linger() {
+            s.field("linger", &linger);
+        }
+        s.finish()
+    }
+}
+
+/// An endpoint a socket is attached to, as returned by
+/// `Socket::endpoints`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SocketEndpoint {
+    /// An endpoint the socket is bound to, in its resolved form.
+    Bound(String),
+    /// An endpoint the socket is connected to.
+    Connected(String),
+}
+
+impl SocketEndpoint {
+    /// Return the endpoint address.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            SocketEndpoint::Bound(ref endpoint) | SocketEndpoint::Connected(ref endpoint) => {
+                endpoint
+            }
+        }
+    }
+}
+
+/// The outcome of receiving a message into a caller-provided buffer, as
+/// returned by `Socket::recv_into`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecvResult {
+    /// The number of bytes written to the buffer.
+    pub received: usize,
+    /// The length of the message, which is larger than `received` if
+    /// the message was truncated to fit the buffer.
+    pub full_len: usize,
+}
+
+impl RecvResult {
+    /// Return `true` if the message did not fit into the buffer.
+    pub fn is_truncated(&self) -> bool {
+        self.full_len > self.received
+    }
+}
+
+/// Convert an endpoint for passing it to libzmq, failing with `EINVAL`
+/// if it contains a NUL byte.
+fn endpoint_c_str(endpoint: &str) -> Result<ffi::CString> {
+    ffi::CString::new(endpoint).map_err(|_| Error::EINVAL)
+}
+
+/// Map `EINVAL` errors to `None`.
+fn skip_einval<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::EINVAL) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Convert the result of a string option getter to a string, replacing
+/// invalid UTF-8 sequences.
+fn lossy(value: result::Result<String, Vec<u8>>) -> String {
+    value.unwrap_or_else(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The values of a socket's options, as returned by
+/// `Socket::options_snapshot`.
+///
+/// Each field corresponds to the `Socket` getter of the same name, and
+/// is `None` if the option is not available. Runtime state like
+/// `ZMQ_EVENTS` and the secret `ZMQ_PLAIN_PASSWORD` and
+/// `ZMQ_CURVE_SECRETKEY` options are not included, so the snapshot can
+/// safely be logged. String options are converted lossily to UTF-8.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SocketOptions {
+    pub socket_type: Option<SocketType>,
+    pub last_endpoint: Option<String>,
+    pub mechanism: Option<Mechanism>,
+    pub routing_id: Option<Vec<u8>>,
+    pub ipv6: Option<bool>,
+    pub immediate: Option<bool>,
+    pub conflate: Option<bool>,
+    pub probe_router: Option<bool>,
+    pub router_mandatory: Option<bool>,
+    pub router_handover: Option<bool>,
+    pub invert_matching: Option<bool>,
+    pub thread_safe: Option<bool>,
+    pub maxmsgsize: Option<i64>,
+    pub sndhwm: Option<i32>,
+    pub rcvhwm: Option<i32>,
+    pub affinity: Option<u64>,
+    pub rate: Option<i32>,
+    pub recovery_ivl: Option<i32>,
+    pub sndbuf: Option<i32>,
+    pub rcvbuf: Option<i32>,
+    pub tos: Option<i32>,
+    pub linger: Option<i32>,
+    pub reconnect_ivl: Option<i32>,
+    pub reconnect_ivl_max: Option<i32>,
+    pub backlog: Option<i32>,
+    pub multicast_hops: Option<i32>,
+    pub multicast_maxtpdu: Option<i32>,
+    pub rcvtimeo: Option<i32>,
+    pub sndtimeo: Option<i32>,
+    pub tcp_keepalive: Option<i32>,
+    pub tcp_keepalive_cnt: Option<i32>,
+    pub tcp_keepalive_idle: Option<i32>,
+    pub tcp_keepalive_intvl: Option<i32>,
+    pub tcp_maxrt: Option<i32>,
+    pub handshake_ivl: Option<i32>,
+    pub heartbeat_ivl: Option<i32>,
+    pub heartbeat_ttl: Option<i32>,
+    pub heartbeat_timeout: Option<i32>,
+    pub connect_timeout: Option<i32>,
+    pub use_fd: Option<i32>,
+    pub bindtodevice: Option<String>,
+    pub socks_proxy: Option<String>,
+    pub plain_server: Option<bool>,
+    pub plain_username: Option<String>,
+    pub curve_server: Option<bool>,
+    pub curve_publickey: Option<Vec<u8>>,
+    pub curve_serverkey: Option<Vec<u8>>,
+    pub gssapi_server: Option<bool>,
+    pub gssapi_plaintext: Option<bool>,
+    pub gssapi_principal: Option<String>,
+    pub gssapi_service_principal: Option<String>,
+    pub gssapi_principal_nametype: Option<i32>,
+    pub gssapi_service_principal_nametype: Option<i32>,
+    pub zap_domain: Option<String>,
+    pub zap_enforce_domain: Option<bool>,
+    pub vmci_buffer_size: Option<u64>,
+    pub vmci_buffer_min_size: Option<u64>,
+    pub vmci_buffer_max_size: Option<u64>,
+    pub vmci_connect_timeout: Option<i32>,
 }
 
 // TODO: Duplicating the values inside the bitflags struct and on the top level
@@ -1088,6 +2440,38 @@ pub fn poll(items: &mut [PollItem], timeout: i64) -> Result<i32> {
     Ok(rc as i32)
 }
 
+/// Poll for events on multiple sockets, with the timeout given as a
+/// duration.
+///
+/// This is like `poll`, except that `None` blocks indefinitely until an
+/// event has occurred. Sub-millisecond remainders of the timeout are
+/// rounded up, and timeouts too long to be represented are capped to
+/// the longest one supported.
+#[allow(clippy::legacy_numeric_constants)] // `c_long::MAX` requires Rust 1.43
+pub fn poll_duration(items: &mut [PollItem], timeout: Option<Duration>) -> Result<i32> {
+    let timeout_ms = match timeout {
+        None => -1,
+        Some(timeout) => cmp::min(sockopt::ceil_millis(timeout), c_long::max_value() as u64) as i64,
+    };
+    poll(items, timeout_ms)
+}
+
+/// Receive an event from a monitor socket, returning the event
+/// number and value.
+fn recv_monitor_event(monitor: &Socket) -> Result<(u16, u32)> {
+    let msg = monitor.recv_msg(0)?;
+    if msg.len() < 6 {
+        return Err(Error::EPROTO);
+    }
+    let event = u16::from_ne_bytes([msg[0], msg[1]]);
+    let value = u32::from_ne_bytes([msg[2], msg[3], msg[4], msg[5]]);
+    // The second frame holds the endpoint the event relates to.
+    if msg.get_more() {
+        monitor.recv_msg(0)?;
+    }
+    Ok((event, value))
+}
+
 /// Start a 0MQ proxy in the current thread.
 ///
 /// A proxy connects a frontend socket with a backend socket, where the exact
@@ -1169,9 +2553,12 @@ pub fn has(capability: &str) -> Option<bool> {
 /// Note that for API consistency reasons, since version 0.9, the key
 /// pair is represented in the binary form. This is in contrast to
 /// libzmq, which returns the z85-encoded representation.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub struct CurveKeyPair {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "config::de::z85_key"))]
     pub public_key: [u8; 32],
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "config::de::z85_key"))]
     pub secret_key: [u8; 32],
 }
 
@@ -1193,118 +2580,82 @@ impl CurveKeyPair {
             public_key: [0; 32],
             secret_key: [0; 32],
         };
-        unsafe {
-            // No need to check return code here, as zmq_curve_keypair
-            // is supposed to generate valid z85-encoded keys
-            zmq_sys::zmq_z85_decode(
-                pair.public_key.as_mut_ptr(),
-                ffi_public_key.as_ptr() as *mut libc::c_char,
-            );
-            zmq_sys::zmq_z85_decode(
-                pair.secret_key.as_mut_ptr(),
-                ffi_secret_key.as_ptr() as *mut libc::c_char,
-            );
-        }
+        // No need to check for errors here, as zmq_curve_keypair is
+        // supposed to generate valid z85-encoded keys
+        let public_key = str::from_utf8(&ffi_public_key[..40]).unwrap();
+        let secret_key = str::from_utf8(&ffi_secret_key[..40]).unwrap();
+        z85::decode_into(public_key, &mut pair.public_key).unwrap();
+        z85::decode_into(secret_key, &mut pair.secret_key).unwrap();
 
         Ok(pair)
     }
 }
 
-/// Errors that can occur while encoding Z85.
-#[derive(Debug)]
-pub enum EncodeError {
-    BadLength,
-    FromUtf8Error(FromUtf8Error),
-}
-
-impl From<FromUtf8Error> for EncodeError {
-    fn from(err: FromUtf8Error) -> Self {
-        EncodeError::FromUtf8Error(err)
-    }
+/// Security configuration of a socket, applied with
+/// `Socket::set_security`.
+///
+/// Each variant describes the complete set of options needed for one
+/// side of a security mechanism. CURVE keys are given in their binary,
+/// 32-byte form; use `z85_decode` to obtain them from their Z85-encoded
+/// representation.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(tag = "mechanism", rename_all = "snake_case")
+)]
+pub enum Security {
+    /// No security; the `ZMQ_NULL` mechanism.
+    Null,
+    /// A `ZMQ_PLAIN` client, authenticating with username and password.
+    PlainClient { username: String, password: String },
+    /// A `ZMQ_PLAIN` server. Credentials are checked by the ZAP handler
+    /// for the given domain, if any.
+    PlainServer { zap_domain: Option<String> },
+    /// A `ZMQ_CURVE` client, using `keypair` to connect to a server
+    /// with the public key `server_key`.
+    CurveClient {
+        #[cfg_attr(feature = "serde", serde(deserialize_with = "config::de::z85_key_vec"))]
+        server_key: Vec<u8>,
+        keypair: CurveKeyPair,
+    },
+    /// A `ZMQ_CURVE` server. Clients are authenticated by the ZAP
+    /// handler for the given domain, if any.
+    CurveServer {
+        keypair: CurveKeyPair,
+        zap_domain: Option<String>,
+    },
 }
 
-impl fmt::Display for EncodeError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            EncodeError::BadLength => write!(f, "Invalid data length. Should be multiple of 4."),
-            EncodeError::FromUtf8Error(ref e) => write!(f, "UTF8 conversion error: {}", e),
+impl Security {
+    /// Return the security mechanism used by this configuration.
+    pub fn mechanism(&self) -> Mechanism {
+        match self {
+            Security::Null => Mechanism::ZMQ_NULL,
+            Security::PlainClient { .. } | Security::PlainServer { .. } => Mechanism::ZMQ_PLAIN,
+            Security::CurveClient { .. } | Security::CurveServer { .. } => Mechanism::ZMQ_CURVE,
         }
     }
 }
 
-impl std::error::Error for EncodeError {}
-
 /// Encode a binary key as Z85 printable text.
 ///
 /// Z85 is an encoding similar to Base64, but operates on 4-byte chunks,
 /// which are encoded into 5-byte sequences.
 ///
-/// The input slice *must* have a length divisible by 4.
+/// The input slice *must* have a length divisible by 4; see
+/// `z85::encode_padded` for encoding data of arbitrary length.
 pub fn z85_encode(data: &[u8]) -> result::Result<String, EncodeError> {
-    if data.len() % 4 != 0 {
-        return Err(EncodeError::BadLength);
-    }
-
-    let len = data.len() * 5 / 4 + 1;
-    let mut dest = vec![0u8; len];
-
-    unsafe {
-        zmq_sys::zmq_z85_encode(
-            dest.as_mut_ptr() as *mut libc::c_char,
-            data.as_ptr(),
-            data.len(),
-        );
-    }
-
-    dest.truncate(len - 1);
-    String::from_utf8(dest).map_err(EncodeError::FromUtf8Error)
-}
-
-/// Errors that can occur while decoding Z85.
-#[derive(Debug)]
-pub enum DecodeError {
-    /// The input string slice's length was not a multiple of 5.
-    BadLength,
-    /// The input string slice had embedded NUL bytes.
-    NulError(ffi::NulError),
+    z85::encode(data)
 }
 
-impl From<ffi::NulError> for DecodeError {
-    fn from(err: ffi::NulError) -> Self {
-        DecodeError::NulError(err)
-    }
-}
-
-impl fmt::Display for DecodeError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            DecodeError::BadLength => write!(f, "Invalid data length. Should be multiple of 5."),
-            DecodeError::NulError(ref e) => write!(f, "Nul byte error: {}", e),
-        }
-    }
-}
-
-impl std::error::Error for DecodeError {}
-
 /// Decode a binary key from Z85-encoded text.
 ///
-/// The input string must have a length divisible by 5.
+/// The input string must have a length divisible by 5; see
+/// `z85::decode_padded` for decoding the padded encoding.
 ///
-/// Note that 0MQ silently accepts characters outside the range defined for
-/// the Z85 encoding.
+/// Characters outside the range defined for the Z85 encoding are
+/// rejected with `DecodeError::BadCharacter`.
 pub fn z85_decode(data: &str) -> result::Result<Vec<u8>, DecodeError> {
-    if data.len() % 5 != 0 {
-        return Err(DecodeError::BadLength);
-    }
-
-    let len = data.len() * 4 / 5;
-    let mut dest = vec![0u8; len];
-
-    let c_str = ffi::CString::new(data)?;
-
-    unsafe {
-        zmq_sys::zmq_z85_decode(dest.as_mut_ptr(), c_str.into_raw());
-    }
-
-    Ok(dest)
+    z85::decode(data)
 }
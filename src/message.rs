@@ -2,11 +2,38 @@ use libc::size_t;
 
 use std::ffi;
 use std::fmt;
+use std::net::IpAddr;
 use std::ops::{Deref, DerefMut};
 use std::os::raw::c_void;
+use std::sync::Arc;
 use std::{ptr, slice, str};
 
-use super::errno_to_error;
+use super::{errno_to_error, Result, SocketType};
+
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
+/// A flag of a message, as accessed by `Message::get_flag` and
+/// `Message::set_flag`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MessageFlag {
+    /// `ZMQ_MORE`: more parts of a multipart message follow.
+    More,
+    /// `ZMQ_SHARED`: the content may be shared with other messages or
+    /// owners, e.g. after `Message::share`, and is copied on mutable
+    /// access, as described for `Message`.
+    Shared,
+}
+
+impl MessageFlag {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            MessageFlag::More => zmq_sys::ZMQ_MORE,
+            MessageFlag::Shared => zmq_sys::ZMQ_SHARED,
+        };
+        raw as i32
+    }
+}
 
 /// Holds a 0MQ message.
 ///
@@ -18,6 +45,16 @@ use super::errno_to_error;
 /// convenience APIs provided (e.g. `Socket::recv_bytes()` or
 /// `Socket::send()`). However, using message objects can make multiple
 /// operations in a loop more efficient, since allocated memory can be reused.
+///
+/// Messages created with `from_static`, from shared data such as an
+/// `Arc<[u8]>`, or with `share`, reference data that must not be
+/// modified. 0MQ marks their content as shared, which carries over to
+/// the messages received from them over inproc transports. Mutable
+/// access to such a message, via `DerefMut`, first copies its content
+/// into a new message owned exclusively, which does not carry over the
+/// properties of the original, such as the `ZMQ_MORE` flag. Other
+/// messages, including those received over the network, are modified in
+/// place.
 pub struct Message {
     msg: zmq_sys::zmq_msg_t,
 }
@@ -41,6 +78,10 @@ unsafe extern "C" fn drop_msg_content_box(data: *mut c_void, _hint: *mut c_void)
     let _ = Box::from_raw(data as *mut u8);
 }
 
+unsafe extern "C" fn drop_msg_content_owner<T>(_data: *mut c_void, hint: *mut c_void) {
+    let _ = Box::from_raw(hint as *mut T);
+}
+
 impl Message {
     unsafe fn alloc<F>(f: F) -> Message
     where
@@ -111,6 +152,107 @@ impl Message {
         Self::from(data)
     }
 
+    /// Create a `Message` referencing the data of `owner` without
+    /// copying it. The owner is dropped once 0MQ no longer needs the
+    /// data, possibly on one of its I/O threads.
+    fn from_owner<T>(owner: T) -> Message
+    where
+        T: AsRef<[u8]> + Send + 'static,
+    {
+        let owner = Box::new(owner);
+        let (data, len) = {
+            let data = (*owner).as_ref();
+            (data.as_ptr(), data.len())
+        };
+        if len == 0 {
+            return Message::new();
+        }
+        let hint = Box::into_raw(owner);
+        let mut msg = unsafe {
+            Self::alloc(|msg| {
+                zmq_sys::zmq_msg_init_data(
+                    msg,
+                    data as *mut c_void,
+                    len,
+                    Some(drop_msg_content_owner::<T>),
+                    hint as *mut c_void,
+                )
+            })
+        };
+        // Sharing the content makes 0MQ mark it as shared; the data
+        // itself may be referenced by other owners.
+        msg.share();
+        msg
+    }
+
+    /// Create a `Message` referencing static data, without allocating or
+    /// copying.
+    ///
+    /// This is useful for frames sent over and over again, like
+    /// delimiters or protocol headers. See also `Socket::send_static`.
+    pub fn from_static(data: &'static [u8]) -> Message {
+        if data.is_empty() {
+            return Message::new();
+        }
+        unsafe {
+            Self::alloc(|msg| {
+                zmq_sys::zmq_msg_init_data(
+                    msg,
+                    data.as_ptr() as *mut c_void,
+                    data.len(),
+                    None,
+                    ptr::null_mut(),
+                )
+            })
+        }
+    }
+
+    /// Create a message sharing the content of this one, using
+    /// `zmq_msg_copy`.
+    ///
+    /// For all but very small messages, the content is not copied, but
+    /// reference-counted, so this is a cheap way of sending the same
+    /// data on several sockets. Both messages become read-only, as
+    /// described for `Message`. This takes `&mut self`, since 0MQ
+    /// updates the original message to mark its content as shared.
+    pub fn share(&mut self) -> Message {
+        let mut msg = Message::new();
+        let rc = unsafe { zmq_sys::zmq_msg_copy(&mut msg.msg, &mut self.msg) };
+        assert_eq!(rc, 0);
+        msg
+    }
+
+    /// Move the content out of this message into a new one, using
+    /// `zmq_msg_move`, leaving this message empty.
+    pub fn take(&mut self) -> Message {
+        let mut msg = Message::new();
+        let rc = unsafe { zmq_sys::zmq_msg_move(&mut msg.msg, &mut self.msg) };
+        assert_eq!(rc, 0);
+        msg
+    }
+
+    /// Convert the message into `Bytes` without copying the data.
+    ///
+    /// The returned `Bytes` keeps the message alive.
+    #[cfg(feature = "bytes")]
+    pub fn into_bytes(self) -> Bytes {
+        struct Owner(Message);
+
+        impl AsRef<[u8]> for Owner {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        Bytes::from_owner(Owner(self))
+    }
+
+    /// Copy the message content into a new `Bytes`.
+    #[cfg(feature = "bytes")]
+    pub fn to_bytes(&self) -> Bytes {
+        Bytes::copy_from_slice(self)
+    }
+
     /// Return the message content as a string slice if it is valid UTF-8.
     pub fn as_str(&self) -> Option<&str> {
         str::from_utf8(self).ok()
@@ -123,6 +265,30 @@ impl Message {
         rc != 0
     }
 
+    /// Return a flag of the message, using `zmq_msg_get`.
+    pub fn get_flag(&self, flag: MessageFlag) -> Result<bool> {
+        let rc = unsafe { zmq_sys::zmq_msg_get(&self.msg, flag.to_raw()) };
+        if rc == -1 {
+            Err(errno_to_error())
+        } else {
+            Ok(rc != 0)
+        }
+    }
+
+    /// Set a flag of the message, using `zmq_msg_set`.
+    ///
+    /// Note that libzmq does not support setting any flags at present,
+    /// so this always fails with `EINVAL`; it is provided for newer
+    /// libzmq versions that might.
+    pub fn set_flag(&mut self, flag: MessageFlag, value: bool) -> Result<()> {
+        let rc = unsafe { zmq_sys::zmq_msg_set(&mut self.msg, flag.to_raw(), value as i32) };
+        if rc == -1 {
+            Err(errno_to_error())
+        } else {
+            Ok(())
+        }
+    }
+
     /// Query a message metadata property.
     ///
     /// # Non-UTF8 values
@@ -131,7 +297,8 @@ impl Message {
     /// value shall be UTF8". However, this is not actually enforced. For API
     /// compatibility reasons, this function will return `None` when
     /// encountering a non-UTF8 value; so a missing and a non-UTF8 value cannot
-    /// currently be distinguished.
+    /// currently be distinguished. Use `metadata` to access the raw
+    /// value instead.
     ///
     /// This is considered a bug in the bindings, and will be fixed with the
     /// next API-breaking release.
@@ -146,6 +313,62 @@ impl Message {
             str::from_utf8(unsafe { ffi::CStr::from_ptr(value) }.to_bytes()).ok()
         }
     }
+
+    /// Query a message metadata property, returning the raw value.
+    ///
+    /// Metadata is attached to messages received from a peer, and
+    /// includes the `Socket-Type`, `Routing-Id` and `Peer-Address`
+    /// properties, as well as the `User-Id` and any properties supplied
+    /// by the ZAP handler when the connection was authenticated.
+    /// Returns `None` if the property is not present.
+    pub fn metadata(&self, property: &str) -> Option<&[u8]> {
+        let c_str = ffi::CString::new(property.as_bytes()).ok()?;
+
+        let value = unsafe { zmq_sys::zmq_msg_gets(&self.msg, c_str.as_ptr()) };
+
+        if value.is_null() {
+            None
+        } else {
+            Some(unsafe { ffi::CStr::from_ptr(value) }.to_bytes())
+        }
+    }
+
+    /// Return the user id the ZAP handler assigned to the peer that
+    /// sent this message, if any.
+    ///
+    /// This is the `User-Id` metadata property.
+    pub fn user_id(&self) -> Option<&[u8]> {
+        self.metadata("User-Id")
+    }
+
+    /// Return the IP address of the peer that sent this message, for
+    /// messages received over TCP.
+    ///
+    /// This is the `Peer-Address` metadata property.
+    pub fn peer_address(&self) -> Option<IpAddr> {
+        let address = str::from_utf8(self.metadata("Peer-Address")?).ok()?;
+        address.parse().ok()
+    }
+
+    /// Return the type of the socket that sent this message, as
+    /// announced by the peer.
+    ///
+    /// This is the `Socket-Type` metadata property. `None` is also
+    /// returned for socket types not known to these bindings.
+    pub fn socket_type(&self) -> Option<SocketType> {
+        SocketType::try_from_name(str::from_utf8(self.metadata("Socket-Type")?).ok()?)
+    }
+
+    /// Return the routing id the peer that sent this message announced,
+    /// if any.
+    ///
+    /// This is the `Routing-Id` metadata property, or `Identity` as
+    /// it is named by libzmq 4.3 and older; see also
+    /// `Socket::set_identity`.
+    pub fn routing_id(&self) -> Option<&[u8]> {
+        self.metadata("Routing-Id")
+            .or_else(|| self.metadata("Identity"))
+    }
 }
 
 impl Deref for Message {
@@ -173,6 +396,9 @@ impl Eq for Message {}
 
 impl DerefMut for Message {
     fn deref_mut(&mut self) -> &mut [u8] {
+        if self.get_flag(MessageFlag::Shared).unwrap_or(true) {
+            *self = Message::from(&self[..]);
+        }
         // This is safe because we're constraining the slice to the lifetime of
         // this message.
         unsafe {
@@ -223,6 +449,30 @@ impl From<Box<[u8]>> for Message {
     }
 }
 
+impl From<Arc<[u8]>> for Message {
+    /// Construct a message from a shared slice without copying the data.
+    ///
+    /// The message holds a reference to the slice until it is released
+    /// by 0MQ, so the same data can be sent on several sockets. Mutable
+    /// access to the message copies the data first, leaving the slice
+    /// unchanged.
+    fn from(data: Arc<[u8]>) -> Self {
+        Message::from_owner(data)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<Bytes> for Message {
+    /// Construct a message from `Bytes` without copying the data.
+    ///
+    /// The message holds a reference to the data until it is released
+    /// by 0MQ, so the same data can be sent on several sockets. Mutable
+    /// access to the message copies the data first.
+    fn from(data: Bytes) -> Self {
+        Message::from_owner(data)
+    }
+}
+
 impl<'a> From<&'a str> for Message {
     /// Construct a message from a string slice by copying the UTF-8 data.
     fn from(msg: &str) -> Self {
@@ -246,6 +496,17 @@ where
     }
 }
 
+/// Create a message whose content is the `len` bytes at `data`, owned
+/// exclusively by the message until 0MQ passes them to `ffn`.
+pub unsafe fn msg_from_data(
+    data: *mut u8,
+    len: usize,
+    ffn: zmq_sys::zmq_free_fn,
+    hint: *mut c_void,
+) -> Message {
+    Message::alloc(|msg| zmq_sys::zmq_msg_init_data(msg, data as *mut c_void, len, ffn, hint))
+}
+
 /// Get the low-level C pointer.
 pub fn msg_ptr(msg: &mut Message) -> *mut zmq_sys::zmq_msg_t {
     &mut msg.msg
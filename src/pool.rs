@@ -0,0 +1,206 @@
+//! Reusable buffers for message content.
+
+use std::fmt;
+use std::os::raw::c_void;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::message::msg_from_data;
+use crate::Message;
+
+// The size up to which 0MQ stores the content of messages inline, on
+// 64-bit platforms.
+const MAX_INLINE_SIZE: usize = 33;
+
+/// A pool of fixed-size buffers for the content of outgoing messages.
+///
+/// Messages created by the pool use one of its buffers as their content,
+/// which goes back to the pool once 0MQ releases the message, typically
+/// after sending it, instead of being freed. This avoids allocating and
+/// freeing the content of each message, but 0MQ still allocates a small
+/// reference count for each of them. Messages of up to 33 bytes are
+/// stored inline by 0MQ without any allocation, so the pool creates
+/// them with `Message::with_size` instead of using a buffer.
+///
+/// At most `capacity` idle buffers are kept; buffers released while the
+/// pool is full are freed. Messages larger than the buffer size are
+/// allocated by 0MQ as usual.
+///
+/// Cloning a pool is cheap, the clones share the same buffers.
+#[derive(Clone)]
+pub struct MessagePool {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    buffer_size: usize,
+    capacity: usize,
+    buffers: Mutex<Vec<Box<[u8]>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    oversized: AtomicUsize,
+    returned: AtomicUsize,
+    discarded: AtomicUsize,
+}
+
+/// Usage statistics of a `MessagePool`, as returned by
+/// `MessagePool::stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// The number of messages created using an idle buffer.
+    pub hits: usize,
+    /// The number of messages for which a buffer had to be allocated,
+    /// because the pool was empty.
+    pub misses: usize,
+    /// The number of messages too large for the buffers.
+    pub oversized: usize,
+    /// The number of buffers that went back to the pool.
+    pub returned: usize,
+    /// The number of buffers freed because the pool was full.
+    pub discarded: usize,
+    /// The number of idle buffers in the pool.
+    pub available: usize,
+}
+
+impl MessagePool {
+    /// Create a pool of buffers of `buffer_size` bytes, keeping at most
+    /// `capacity` idle buffers.
+    ///
+    /// Buffers are allocated on demand; use `reserve` to allocate them
+    /// up front.
+    pub fn new(buffer_size: usize, capacity: usize) -> MessagePool {
+        MessagePool {
+            inner: Arc::new(Inner {
+                buffer_size,
+                capacity,
+                buffers: Mutex::new(Vec::with_capacity(capacity)),
+                hits: AtomicUsize::new(0),
+                misses: AtomicUsize::new(0),
+                oversized: AtomicUsize::new(0),
+                returned: AtomicUsize::new(0),
+                discarded: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Allocate idle buffers until there are `count` of them, or the
+    /// pool is full.
+    pub fn reserve(&self, count: usize) {
+        let mut buffers = self.inner.lock();
+        let count = count.min(self.inner.capacity);
+        while buffers.len() < count {
+            buffers.push(self.inner.alloc());
+        }
+    }
+
+    /// Return the size of the buffers.
+    pub fn buffer_size(&self) -> usize {
+        self.inner.buffer_size
+    }
+
+    /// Return the maximum number of idle buffers kept.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+
+    /// Return the usage statistics of the pool.
+    pub fn stats(&self) -> PoolStats {
+        let inner = &self.inner;
+        PoolStats {
+            hits: inner.hits.load(Ordering::Relaxed),
+            misses: inner.misses.load(Ordering::Relaxed),
+            oversized: inner.oversized.load(Ordering::Relaxed),
+            returned: inner.returned.load(Ordering::Relaxed),
+            discarded: inner.discarded.load(Ordering::Relaxed),
+            available: inner.lock().len(),
+        }
+    }
+
+    /// Create a message of `len` bytes that are initialized to 0.
+    ///
+    /// This is the pooled equivalent of `Message::with_size`.
+    pub fn message(&self, len: usize) -> Message {
+        self.message_with(len, |data| {
+            for byte in data {
+                *byte = 0;
+            }
+        })
+    }
+
+    /// Create a message holding a copy of `data`.
+    pub fn copy_from(&self, data: &[u8]) -> Message {
+        self.message_with(data.len(), |content| content.copy_from_slice(data))
+    }
+
+    fn message_with<F>(&self, len: usize, init: F) -> Message
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        if len <= MAX_INLINE_SIZE || len > self.inner.buffer_size {
+            if len > self.inner.buffer_size {
+                self.inner.oversized.fetch_add(1, Ordering::Relaxed);
+            }
+            let mut msg = Message::with_size(len);
+            init(&mut msg);
+            return msg;
+        }
+        let mut buffer = self.inner.take();
+        init(&mut buffer[..len]);
+        let data = Box::into_raw(buffer) as *mut u8;
+        let hint = Arc::into_raw(Arc::clone(&self.inner));
+        unsafe { msg_from_data(data, len, Some(release_buffer), hint as *mut c_void) }
+    }
+}
+
+impl fmt::Debug for MessagePool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MessagePool")
+            .field("buffer_size", &self.inner.buffer_size)
+            .field("capacity", &self.inner.capacity)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl Inner {
+    // The free function runs on 0MQ threads, where panicking is not an
+    // option, so poisoning is ignored.
+    fn lock(&self) -> MutexGuard<'_, Vec<Box<[u8]>>> {
+        self.buffers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn alloc(&self) -> Box<[u8]> {
+        vec![0; self.buffer_size].into_boxed_slice()
+    }
+
+    fn take(&self) -> Box<[u8]> {
+        let buffer = self.lock().pop();
+        match buffer {
+            Some(buffer) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buffer
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                self.alloc()
+            }
+        }
+    }
+
+    fn put(&self, buffer: Box<[u8]>) {
+        let mut buffers = self.lock();
+        if buffers.len() < self.capacity {
+            buffers.push(buffer);
+            self.returned.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.discarded.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+unsafe extern "C" fn release_buffer(data: *mut c_void, hint: *mut c_void) {
+    let inner = Arc::from_raw(hint as *const Inner);
+    let buffer = slice::from_raw_parts_mut(data as *mut u8, inner.buffer_size);
+    inner.put(Box::from_raw(buffer as *mut [u8]));
+}
@@ -2,9 +2,10 @@ use libc::{c_int, c_uint, size_t};
 use std::os::raw::c_void;
 use std::result;
 use std::string::FromUtf8Error;
+use std::time::Duration;
 use std::{mem, ptr, str};
 
-use super::{PollEvents, Result};
+use super::{Error, PollEvents, Result};
 
 pub trait Getter
 where
@@ -157,3 +158,45 @@ pub fn get<T: Getter>(sock: *mut c_void, opt: c_int) -> Result<T> {
 pub fn set<T: Setter>(sock: *mut c_void, opt: c_int, value: T) -> Result<()> {
     T::set(sock, opt, value)
 }
+
+/// Return the number of milliseconds in `duration`, rounding up any
+/// sub-millisecond remainder, and saturating on overflow.
+pub fn ceil_millis(duration: Duration) -> u64 {
+    let nanos = duration.subsec_nanos();
+    let mut millis = u64::from(nanos / 1_000_000);
+    if millis * 1_000_000 < u64::from(nanos) {
+        millis += 1;
+    }
+    duration
+        .as_secs()
+        .saturating_mul(1000)
+        .saturating_add(millis)
+}
+
+/// Convert an optional duration to the milliseconds value of a time
+/// option, with `none` being the value that means "no limit".
+///
+/// Sub-millisecond remainders are rounded up, so that a non-zero duration
+/// never turns into a zero timeout. Durations that do not fit into an
+/// `i32`, or would be mistaken for `none`, are rejected with `EINVAL`.
+#[allow(clippy::legacy_numeric_constants)] // `i32::MAX` requires Rust 1.43
+pub fn duration_to_ms(value: Option<Duration>, none: i32) -> Result<i32> {
+    let ms = match value {
+        Some(duration) => ceil_millis(duration),
+        None => return Ok(none),
+    };
+    if ms > i32::max_value() as u64 || ms as i32 == none {
+        return Err(Error::EINVAL);
+    }
+    Ok(ms as i32)
+}
+
+/// Convert the milliseconds value of a time option to an optional
+/// duration; the inverse of `duration_to_ms`.
+pub fn duration_from_ms(ms: i32, none: i32) -> Option<Duration> {
+    if ms == none || ms < 0 {
+        None
+    } else {
+        Some(Duration::from_millis(ms as u64))
+    }
+}
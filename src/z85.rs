@@ -0,0 +1,541 @@
+//! Z85 encoding, as specified by [ZMQ RFC 32].
+//!
+//! Z85 is an encoding similar to Base64, but operates on 4-byte
+//! chunks, which are encoded into 5-byte sequences. This module
+//! implements it in pure Rust, without calling into libzmq; the output
+//! is identical to that of libzmq's `zmq_z85_encode`, and the same
+//! input is accepted as by `zmq_z85_decode`.
+//!
+//! Plain Z85, as implemented by `encode` and `decode`, only handles
+//! input whose length is a multiple of 4 bytes. In addition, this
+//! module provides a padded variant of the encoding that handles input
+//! of any length, functions that encode into and decode from
+//! caller-provided buffers without allocating, and the streaming
+//! adapters `Encoder` and `Decoder`.
+//!
+//! # Padding
+//!
+//! The padded encoding extends the input with zero bytes up to the
+//! next multiple of 4 bytes, encodes the result as plain Z85, and
+//! appends a single marker character stating how many padding bytes
+//! were added; the marker is one of `0`, `1`, `2` or `3`. The length of
+//! a padded encoding is thus always one more than a multiple of 5, and
+//! the empty input is encoded as `"0"`.
+//!
+//! ```
+//! let encoded = zmq::z85::encode_padded(b"hello");
+//! assert_eq!(encoded, "xK#0@zVx+q3");
+//! assert_eq!(zmq::z85::decode_padded(&encoded).unwrap(), b"hello");
+//! ```
+//!
+//! [ZMQ RFC 32]: https://rfc.zeromq.org/spec:32/Z85/
+
+use std::ffi;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::string::FromUtf8Error;
+use std::{cmp, result};
+
+const ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+// Maps the characters from `' '` to `'\x7f'` to their value, or `0xFF`
+// for characters outside the alphabet.
+const DECODER: [u8; 96] = [
+    0xFF, 0x44, 0xFF, 0x54, 0x53, 0x52, 0x48, 0xFF, 0x4B, 0x4C, 0x46, 0x41, 0xFF, 0x3F, 0x3E, 0x45,
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x40, 0xFF, 0x49, 0x42, 0x4A, 0x47,
+    0x51, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x2B, 0x2C, 0x2D, 0x2E, 0x2F, 0x30, 0x31, 0x32,
+    0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x3B, 0x3C, 0x3D, 0x4D, 0xFF, 0x4E, 0x43, 0xFF,
+    0xFF, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18,
+    0x19, 0x1A, 0x1B, 0x1C, 0x1D, 0x1E, 0x1F, 0x20, 0x21, 0x22, 0x23, 0x4F, 0xFF, 0x50, 0xFF, 0xFF,
+];
+
+/// Errors that can occur while encoding Z85.
+#[derive(Debug)]
+pub enum EncodeError {
+    BadLength,
+    FromUtf8Error(FromUtf8Error),
+    /// The output buffer was too small to hold the encoded data.
+    BufferTooSmall,
+}
+
+impl From<FromUtf8Error> for EncodeError {
+    fn from(err: FromUtf8Error) -> Self {
+        EncodeError::FromUtf8Error(err)
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncodeError::BadLength => write!(f, "Invalid data length. Should be multiple of 4."),
+            EncodeError::FromUtf8Error(ref e) => write!(f, "UTF8 conversion error: {}", e),
+            EncodeError::BufferTooSmall => write!(f, "Output buffer too small."),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+/// Errors that can occur while decoding Z85.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The input string slice's length was not a multiple of 5.
+    BadLength,
+    /// The input string slice had embedded NUL bytes.
+    NulError(ffi::NulError),
+    /// The input contained a character outside the Z85 alphabet, at
+    /// the given byte offset.
+    BadCharacter(usize),
+    /// The block starting at the given byte offset encodes a value
+    /// that does not fit into 4 bytes.
+    Overflow(usize),
+    /// The padding marker of padded Z85 text was missing or invalid.
+    BadPadding,
+    /// The output buffer was too small to hold the decoded data.
+    BufferTooSmall,
+}
+
+impl From<ffi::NulError> for DecodeError {
+    fn from(err: ffi::NulError) -> Self {
+        DecodeError::NulError(err)
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::BadLength => write!(f, "Invalid data length. Should be multiple of 5."),
+            DecodeError::NulError(ref e) => write!(f, "Nul byte error: {}", e),
+            DecodeError::BadCharacter(pos) => write!(f, "Invalid character at offset {}.", pos),
+            DecodeError::Overflow(pos) => write!(f, "Block at offset {} out of range.", pos),
+            DecodeError::BadPadding => write!(f, "Invalid padding."),
+            DecodeError::BufferTooSmall => write!(f, "Output buffer too small."),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Encode binary data as Z85 printable text.
+///
+/// The input slice *must* have a length divisible by 4; use
+/// `encode_padded` for data of arbitrary length.
+pub fn encode(data: &[u8]) -> result::Result<String, EncodeError> {
+    let mut dest = vec![0u8; data.len() / 4 * 5];
+    encode_into(data, &mut dest)?;
+    String::from_utf8(dest).map_err(EncodeError::FromUtf8Error)
+}
+
+/// Decode binary data from Z85-encoded text.
+///
+/// The input string must have a length divisible by 5; use
+/// `decode_padded` for text produced by `encode_padded`.
+///
+/// For compatibility with `z85_decode`, embedded NUL bytes are reported
+/// as `DecodeError::NulError`, while other characters outside the Z85
+/// alphabet are reported as `DecodeError::BadCharacter`.
+pub fn decode(data: &str) -> result::Result<Vec<u8>, DecodeError> {
+    if data.as_bytes().contains(&0) {
+        return Err(ffi::CString::new(data).unwrap_err().into());
+    }
+    let mut dest = vec![0u8; data.len() / 5 * 4];
+    decode_into(data, &mut dest)?;
+    Ok(dest)
+}
+
+/// Return the length of the padded encoding of `len` bytes of input.
+#[allow(clippy::manual_div_ceil)] // `usize::div_ceil` requires Rust 1.73
+pub fn padded_encoded_len(len: usize) -> usize {
+    (len + 3) / 4 * 5 + 1
+}
+
+/// Encode data of any length as padded Z85 text.
+pub fn encode_padded(data: &[u8]) -> String {
+    let mut dest = vec![0u8; padded_encoded_len(data.len())];
+    encode_padded_into(data, &mut dest).unwrap();
+    String::from_utf8(dest).unwrap()
+}
+
+/// Decode padded Z85 text, as produced by `encode_padded`.
+pub fn decode_padded(data: &str) -> result::Result<Vec<u8>, DecodeError> {
+    let mut dest = vec![0u8; padded_decoded_len(data.as_bytes())?];
+    decode_padded_into(data, &mut dest)?;
+    Ok(dest)
+}
+
+/// Encode data as plain Z85 text into a caller-provided buffer.
+///
+/// The input slice *must* have a length divisible by 4, and `dest`
+/// must have room for at least `data.len() / 4 * 5` bytes. Returns the
+/// number of bytes written to `dest`.
+pub fn encode_into(data: &[u8], dest: &mut [u8]) -> result::Result<usize, EncodeError> {
+    let rem = data.len() % 4;
+    if rem != 0 {
+        return Err(EncodeError::BadLength);
+    }
+    let len = data.len() / 4 * 5;
+    if dest.len() < len {
+        return Err(EncodeError::BufferTooSmall);
+    }
+    encode_blocks(data, dest);
+    Ok(len)
+}
+
+/// Encode data of any length as padded Z85 text into a
+/// caller-provided buffer.
+///
+/// `dest` must have room for at least `padded_encoded_len(data.len())`
+/// bytes. Returns the number of bytes written to `dest`.
+pub fn encode_padded_into(data: &[u8], dest: &mut [u8]) -> result::Result<usize, EncodeError> {
+    let len = padded_encoded_len(data.len());
+    if dest.len() < len {
+        return Err(EncodeError::BufferTooSmall);
+    }
+    let (blocks, tail) = data.split_at(data.len() / 4 * 4);
+    let mut n = blocks.len() / 4 * 5;
+    encode_blocks(blocks, &mut dest[..n]);
+    let padding = (4 - tail.len()) % 4;
+    if !tail.is_empty() {
+        let mut block = [0u8; 4];
+        block[..tail.len()].copy_from_slice(tail);
+        encode_block(&block, &mut dest[n..n + 5]);
+        n += 5;
+    }
+    dest[n] = ALPHABET[padding];
+    Ok(len)
+}
+
+/// Decode plain Z85 text into a caller-provided buffer.
+///
+/// The input string must have a length divisible by 5, and `dest` must
+/// have room for at least `data.len() / 5 * 4` bytes. Returns the
+/// number of bytes written to `dest`.
+pub fn decode_into(data: &str, dest: &mut [u8]) -> result::Result<usize, DecodeError> {
+    let data = data.as_bytes();
+    let rem = data.len() % 5;
+    if rem != 0 {
+        return Err(DecodeError::BadLength);
+    }
+    let len = data.len() / 5 * 4;
+    if dest.len() < len {
+        return Err(DecodeError::BufferTooSmall);
+    }
+    decode_blocks(data, 0, dest)?;
+    Ok(len)
+}
+
+/// Decode padded Z85 text into a caller-provided buffer.
+///
+/// `dest` must have room for the decoded data, which is the length of
+/// the input minus the marker character, times 4/5, minus the number
+/// of padding bytes. Returns the number of bytes written to `dest`.
+pub fn decode_padded_into(data: &str, dest: &mut [u8]) -> result::Result<usize, DecodeError> {
+    decode_padded_at(data.as_bytes(), 0, dest)
+}
+
+/// Split padded Z85 text into the encoded blocks and the number of
+/// padding bytes indicated by the marker character.
+fn split_padded(data: &[u8]) -> result::Result<(&[u8], usize), DecodeError> {
+    let (marker, blocks) = match data.split_last() {
+        Some((&marker, blocks)) if blocks.len() % 5 == 0 => (marker, blocks),
+        _ => return Err(DecodeError::BadPadding),
+    };
+    let padding = match marker {
+        b'0'..=b'3' => usize::from(marker - b'0'),
+        _ => return Err(DecodeError::BadPadding),
+    };
+    if blocks.is_empty() && padding != 0 {
+        return Err(DecodeError::BadPadding);
+    }
+    Ok((blocks, padding))
+}
+
+fn padded_decoded_len(data: &[u8]) -> result::Result<usize, DecodeError> {
+    let (blocks, padding) = split_padded(data)?;
+    Ok(blocks.len() / 5 * 4 - padding)
+}
+
+/// Decode padded Z85 text starting at position `offset` of the whole
+/// input, which is used for error reporting.
+fn decode_padded_at(
+    data: &[u8],
+    offset: usize,
+    dest: &mut [u8],
+) -> result::Result<usize, DecodeError> {
+    let (blocks, padding) = split_padded(data)?;
+    let len = blocks.len() / 5 * 4 - padding;
+    if dest.len() < len {
+        return Err(DecodeError::BufferTooSmall);
+    }
+    if padding == 0 {
+        decode_blocks(blocks, offset, dest)?;
+        return Ok(len);
+    }
+    let (full, last) = blocks.split_at(blocks.len() - 5);
+    let n = full.len() / 5 * 4;
+    decode_blocks(full, offset, dest)?;
+    let mut block = [0u8; 4];
+    decode_block(last, offset + full.len(), &mut block)?;
+    let (value, zeros) = block.split_at(4 - padding);
+    if zeros.iter().any(|&b| b != 0) {
+        return Err(DecodeError::BadPadding);
+    }
+    dest[n..len].copy_from_slice(value);
+    Ok(len)
+}
+
+fn encode_blocks(data: &[u8], dest: &mut [u8]) {
+    for (src, dst) in data.chunks(4).zip(dest.chunks_mut(5)) {
+        encode_block(src, dst);
+    }
+}
+
+fn decode_blocks(data: &[u8], offset: usize, dest: &mut [u8]) -> result::Result<(), DecodeError> {
+    for (i, (src, dst)) in data.chunks(5).zip(dest.chunks_mut(4)).enumerate() {
+        decode_block(src, offset + i * 5, dst)?;
+    }
+    Ok(())
+}
+
+/// Encode a single 4-byte block into 5 characters.
+fn encode_block(src: &[u8], dest: &mut [u8]) {
+    let mut value = u32::from(src[0]) << 24
+        | u32::from(src[1]) << 16
+        | u32::from(src[2]) << 8
+        | u32::from(src[3]);
+    for c in dest[..5].iter_mut().rev() {
+        *c = ALPHABET[(value % 85) as usize];
+        value /= 85;
+    }
+}
+
+/// Decode a single block of 5 characters into 4 bytes.
+///
+/// Like `zmq_z85_decode`, this rejects characters outside the alphabet
+/// and blocks representing values that do not fit into 32 bits.
+fn decode_block(src: &[u8], offset: usize, dest: &mut [u8]) -> result::Result<(), DecodeError> {
+    let mut value: u32 = 0;
+    for (i, &c) in src[..5].iter().enumerate() {
+        let digit = match c.checked_sub(32).and_then(|i| DECODER.get(usize::from(i))) {
+            Some(&digit) if digit != 0xFF => u32::from(digit),
+            _ => return Err(DecodeError::BadCharacter(offset + i)),
+        };
+        value = match value.checked_mul(85).and_then(|v| v.checked_add(digit)) {
+            Some(value) => value,
+            None => return Err(DecodeError::Overflow(offset)),
+        };
+    }
+    dest[0] = (value >> 24) as u8;
+    dest[1] = (value >> 16) as u8;
+    dest[2] = (value >> 8) as u8;
+    dest[3] = value as u8;
+    Ok(())
+}
+
+// Number of blocks the streaming adapters process at once.
+const STREAM_BLOCKS: usize = 128;
+
+/// A writer that encodes everything written to it as padded Z85 text.
+///
+/// The data written is passed on to the underlying writer in blocks;
+/// the last, possibly partial, block and the padding marker are only
+/// written when calling `finish`. Dropping the encoder finishes it as
+/// well, but ignores any errors that occur while doing so.
+///
+/// ```
+/// use std::io::Write;
+///
+/// let mut encoder = zmq::z85::Encoder::new(Vec::new());
+/// encoder.write_all(b"hel").unwrap();
+/// encoder.write_all(b"lo").unwrap();
+/// let encoded = encoder.finish().unwrap();
+/// assert_eq!(encoded, zmq::z85::encode_padded(b"hello").as_bytes());
+/// ```
+pub struct Encoder<W: Write> {
+    inner: Option<W>,
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Create an encoder writing Z85 text to `inner`.
+    pub fn new(inner: W) -> Encoder<W> {
+        Encoder {
+            inner: Some(inner),
+            pending: [0; 4],
+            pending_len: 0,
+        }
+    }
+
+    /// Return a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    /// Write the remaining data and the padding marker, and return the
+    /// underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_tail()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn write_tail(&mut self) -> io::Result<()> {
+        let mut buf = [0u8; 6];
+        let n = encode_padded_into(&self.pending[..self.pending_len], &mut buf).unwrap();
+        self.pending_len = 0;
+        self.inner.as_mut().unwrap().write_all(&buf[..n])
+    }
+
+    fn write_blocks(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut buf = [0u8; STREAM_BLOCKS * 5];
+        for chunk in data.chunks(STREAM_BLOCKS * 4) {
+            let n = chunk.len() / 4 * 5;
+            encode_blocks(chunk, &mut buf[..n]);
+            self.inner.as_mut().unwrap().write_all(&buf[..n])?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let mut rest = data;
+        if self.pending_len > 0 {
+            let n = cmp::min(4 - self.pending_len, rest.len());
+            self.pending[self.pending_len..self.pending_len + n].copy_from_slice(&rest[..n]);
+            self.pending_len += n;
+            rest = &rest[n..];
+            if self.pending_len < 4 {
+                return Ok(data.len());
+            }
+            let block = self.pending;
+            self.pending_len = 0;
+            self.write_blocks(&block)?;
+        }
+        let (blocks, tail) = rest.split_at(rest.len() / 4 * 4);
+        self.write_blocks(blocks)?;
+        self.pending[..tail.len()].copy_from_slice(tail);
+        self.pending_len = tail.len();
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for Encoder<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_tail();
+        }
+    }
+}
+
+/// A reader that decodes padded Z85 text read from an underlying
+/// reader.
+///
+/// Malformed input is reported as an `io::Error` of kind
+/// `InvalidData`, wrapping the corresponding `DecodeError`.
+///
+/// ```
+/// use std::io::Read;
+///
+/// let encoded = zmq::z85::encode_padded(b"hello");
+/// let mut decoder = zmq::z85::Decoder::new(encoded.as_bytes());
+/// let mut decoded = Vec::new();
+/// decoder.read_to_end(&mut decoded).unwrap();
+/// assert_eq!(decoded, b"hello");
+/// ```
+pub struct Decoder<R: Read> {
+    inner: R,
+    // Input is only decoded once it is known that the block is
+    // followed by at least two more characters, i.e. it is neither
+    // the last block nor the padding marker.
+    input: [u8; STREAM_BLOCKS * 5 + 2],
+    input_len: usize,
+    output: [u8; STREAM_BLOCKS * 4],
+    output_pos: usize,
+    output_len: usize,
+    offset: usize,
+    done: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    /// Create a decoder reading Z85 text from `inner`.
+    pub fn new(inner: R) -> Decoder<R> {
+        Decoder {
+            inner,
+            input: [0; STREAM_BLOCKS * 5 + 2],
+            input_len: 0,
+            output: [0; STREAM_BLOCKS * 4],
+            output_pos: 0,
+            output_len: 0,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Return a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Consume the decoder, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn fill_output(&mut self) -> io::Result<()> {
+        loop {
+            let n = match self.inner.read(&mut self.input[self.input_len..]) {
+                Ok(n) => n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if n == 0 {
+                self.done = true;
+                let data = &self.input[..self.input_len];
+                self.output_len =
+                    decode_padded_at(data, self.offset, &mut self.output).map_err(invalid_data)?;
+                self.output_pos = 0;
+                return Ok(());
+            }
+            self.input_len += n;
+            if self.input_len >= 7 {
+                let consumed = (self.input_len - 2) / 5 * 5;
+                decode_blocks(&self.input[..consumed], self.offset, &mut self.output)
+                    .map_err(invalid_data)?;
+                self.input[..self.input_len].rotate_left(consumed);
+                self.input_len -= consumed;
+                self.offset += consumed;
+                self.output_pos = 0;
+                self.output_len = consumed / 5 * 4;
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.output_pos == self.output_len {
+            if self.done {
+                return Ok(0);
+            }
+            self.fill_output()?;
+        }
+        let n = cmp::min(buf.len(), self.output_len - self.output_pos);
+        buf[..n].copy_from_slice(&self.output[self.output_pos..self.output_pos + n]);
+        self.output_pos += n;
+        Ok(n)
+    }
+}
+
+fn invalid_data(e: DecodeError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
@@ -0,0 +1,210 @@
+#[macro_use]
+mod common;
+
+use std::time::Duration;
+use zmq::{Context, Error, Mechanism, Security, SocketConfig};
+
+test!(test_socket_from_config, {
+    let ctx = Context::new();
+    let mut config = SocketConfig::new(zmq::SUB);
+    config.rcvhwm = Some(10);
+    config.linger = Some(Some(Duration::from_millis(0)));
+    config.rcvtimeo = Some(Some(Duration::from_secs(2)));
+    config.sndtimeo = Some(None);
+    config.routing_id = Some(b"subscriber".to_vec());
+    config.subscribe = vec![b"topic".to_vec()];
+    config.bind = vec!["inproc://config".into()];
+
+    let sub = ctx.socket_from_config(&config).unwrap();
+    assert_eq!(sub.get_socket_type().unwrap(), zmq::SUB);
+    assert_eq!(sub.get_rcvhwm().unwrap(), 10);
+    assert_eq!(sub.get_linger().unwrap(), 0);
+    assert_eq!(sub.get_rcvtimeo().unwrap(), 2000);
+    assert_eq!(sub.get_sndtimeo().unwrap(), -1);
+    assert_eq!(sub.get_identity().unwrap(), b"subscriber");
+    assert_eq!(sub.get_last_endpoint().unwrap().unwrap(), "inproc://config");
+
+    let publisher = ctx.socket(zmq::PUB).unwrap();
+    publisher.connect("inproc://config").unwrap();
+    loop {
+        publisher.send("other", 0).unwrap();
+        publisher.send("topic", 0).unwrap();
+        if sub.poll(zmq::POLLIN, 100).unwrap() != 0 {
+            break;
+        }
+    }
+    assert_eq!(sub.recv_bytes(0).unwrap(), b"topic");
+});
+
+test!(test_socket_from_config_security, {
+    let ctx = Context::new();
+    let mut config = SocketConfig::new(zmq::REQ);
+    config.security = Some(Security::PlainClient {
+        username: "admin".into(),
+        password: "secret".into(),
+    });
+    let sock = ctx.socket_from_config(&config).unwrap();
+    assert_eq!(sock.get_mechanism().unwrap(), Mechanism::ZMQ_PLAIN);
+
+    config.security = Some(Security::PlainClient {
+        username: String::new(),
+        password: "secret".into(),
+    });
+    let err = ctx.socket_from_config(&config).unwrap_err();
+    assert_eq!(err.field(), "security");
+    assert_eq!(err.error(), Error::EINVAL);
+});
+
+test!(test_socket_from_config_error, {
+    let ctx = Context::new();
+    let mut config = SocketConfig::new(zmq::PULL);
+    config.bind = vec![
+        "inproc://config_error".into(),
+        "bogus://config_error".into(),
+    ];
+    let err = ctx.socket_from_config(&config).unwrap_err();
+    assert_eq!(err.field(), "bind[1]");
+    assert_eq!(err.error(), Error::EPROTONOSUPPORT);
+    assert_eq!(
+        err.to_string(),
+        "invalid socket configuration `bind[1]`: Protocol not supported"
+    );
+
+    // The partially configured socket has been closed, releasing the
+    // endpoint bound before the failure.
+    let sock = ctx.socket(zmq::PULL).unwrap();
+    sock.bind("inproc://config_error").unwrap();
+
+    // Subscriptions only apply to SUB sockets.
+    let mut config = SocketConfig::new(zmq::PULL);
+    config.subscribe = vec![b"topic".to_vec()];
+    let err = ctx.socket_from_config(&config).unwrap_err();
+    assert_eq!(err.field(), "subscribe[0]");
+    assert_eq!(err.error(), Error::EINVAL);
+});
+
+#[cfg(feature = "serde")]
+mod serde {
+    use std::time::Duration;
+    use zmq::{Context, Mechanism, Security, SocketConfig};
+
+    test!(test_config_from_toml, {
+        let config: SocketConfig = toml::from_str(
+            r#"
+            type = "DEALER"
+            sndhwm = 100
+            linger = 0
+            rcvtimeo = -1
+            heartbeat_ivl = 500
+            routing_id = "dealer"
+            connect = ["inproc://config_toml"]
+
+            [security]
+            mechanism = "plain_client"
+            username = "admin"
+            password = "secret"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.socket_type, zmq::DEALER);
+        assert_eq!(config.sndhwm, Some(100));
+        assert_eq!(config.rcvhwm, None);
+        assert_eq!(config.linger, Some(Some(Duration::from_millis(0))));
+        assert_eq!(config.rcvtimeo, Some(None));
+        assert_eq!(config.sndtimeo, None);
+        assert_eq!(config.routing_id, Some(b"dealer".to_vec()));
+        assert_eq!(config.connect, vec!["inproc://config_toml"]);
+        assert!(config.bind.is_empty());
+
+        let ctx = Context::new();
+        let sock = ctx.socket_from_config(&config).unwrap();
+        assert_eq!(sock.get_sndhwm().unwrap(), 100);
+        assert_eq!(sock.get_heartbeat_ivl().unwrap(), 500);
+        assert_eq!(sock.get_mechanism().unwrap(), Mechanism::ZMQ_PLAIN);
+        assert_eq!(sock.get_plain_username().unwrap().unwrap(), "admin");
+    });
+
+    test!(test_config_zero_durations_from_toml, {
+        // For options whose special value is 0, a 0 stands for `None`,
+        // rather than a duration that the setters would reject.
+        let config: SocketConfig = toml::from_str(
+            r#"
+            type = "DEALER"
+            linger = 0
+            reconnect_ivl_max = 0
+            connect_timeout = 0
+            handshake_ivl = 0
+            heartbeat_ivl = 0
+            heartbeat_ttl = 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.linger, Some(Some(Duration::from_millis(0))));
+        assert_eq!(config.reconnect_ivl_max, Some(None));
+        assert_eq!(config.connect_timeout, Some(None));
+        assert_eq!(config.handshake_ivl, Some(None));
+        assert_eq!(config.heartbeat_ivl, Some(None));
+        assert_eq!(config.heartbeat_ttl, Some(None));
+
+        let ctx = Context::new();
+        let sock = ctx.socket_from_config(&config).unwrap();
+        assert_eq!(sock.get_linger().unwrap(), 0);
+        assert_eq!(sock.get_reconnect_ivl_max().unwrap(), 0);
+        assert_eq!(sock.get_connect_timeout().unwrap(), 0);
+        assert_eq!(sock.get_handshake_ivl().unwrap(), 0);
+        assert_eq!(sock.get_heartbeat_ivl().unwrap(), 0);
+        assert_eq!(sock.get_heartbeat_ttl().unwrap(), 0);
+    });
+
+    test!(test_config_curve_from_toml, {
+        let config: SocketConfig = toml::from_str(
+            r#"
+            type = "REP"
+            subscribe = []
+
+            [security]
+            mechanism = "curve_server"
+            zap_domain = "global"
+
+            [security.keypair]
+            public_key = "Yne@$w-vo<fVvi]a<NY6T1ed:M$fCG*[IaLV{hID"
+            secret_key = "D:)Q[IlAW!ahhC2ac:9*A}h:p?([4%wOTJ%JR%cs"
+            "#,
+        )
+        .unwrap();
+        match config.security {
+            Some(Security::CurveServer {
+                ref keypair,
+                ref zap_domain,
+            }) => {
+                assert_eq!(
+                    zmq::z85_encode(&keypair.public_key).unwrap(),
+                    "Yne@$w-vo<fVvi]a<NY6T1ed:M$fCG*[IaLV{hID"
+                );
+                assert_eq!(zap_domain.as_ref().unwrap(), "global");
+            }
+            ref other => panic!("unexpected security configuration: {:?}", other),
+        }
+    });
+
+    test!(test_config_invalid_toml, {
+        // Unknown keys are rejected, rather than silently ignored.
+        let result: Result<SocketConfig, _> = toml::from_str("type = \"REQ\"\nsndwhm = 1\n");
+        assert!(result.unwrap_err().to_string().contains("sndwhm"));
+
+        let result: Result<SocketConfig, _> = toml::from_str(
+            r#"
+            type = "REQ"
+
+            [security]
+            mechanism = "curve_client"
+            server_key = "too short"
+
+            [security.keypair]
+            public_key = "Yne@$w-vo<fVvi]a<NY6T1ed:M$fCG*[IaLV{hID"
+            secret_key = "D:)Q[IlAW!ahhC2ac:9*A}h:p?([4%wOTJ%JR%cs"
+            "#,
+        );
+        assert!(result.unwrap_err().to_string().contains("Z85"));
+    });
+}
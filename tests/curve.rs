@@ -1,7 +1,7 @@
 #[macro_use]
 mod common;
 
-use zmq::{z85_decode, Context, CurveKeyPair, Socket};
+use zmq::{z85_decode, Context, CurveKeyPair, Error, Mechanism, Security, Socket};
 
 fn create_socketpair() -> (Socket, Socket) {
     let ctx = Context::default();
@@ -76,3 +76,67 @@ test_capability!(test_getset_curve_serverkey, "curve", {
     sock.set_curve_serverkey(&key).unwrap();
     assert_eq!(sock.get_curve_serverkey().unwrap(), key);
 });
+
+test_capability!(test_curve_security, "curve", {
+    let ctx = Context::new();
+    let server = ctx.socket(zmq::REP).unwrap();
+    let client = ctx.socket(zmq::REQ).unwrap();
+    let server_pair = CurveKeyPair::new().unwrap();
+
+    server
+        .set_security(Security::CurveServer {
+            keypair: server_pair.clone(),
+            zap_domain: Some("test".into()),
+        })
+        .unwrap();
+    assert_eq!(server.get_mechanism().unwrap(), Mechanism::ZMQ_CURVE);
+    assert!(server.is_curve_server().unwrap());
+    assert_eq!(server.get_zap_domain().unwrap().unwrap(), "test");
+
+    client
+        .set_security(Security::CurveClient {
+            server_key: server_pair.public_key.to_vec(),
+            keypair: CurveKeyPair::new().unwrap(),
+        })
+        .unwrap();
+    assert_eq!(client.get_mechanism().unwrap(), Mechanism::ZMQ_CURVE);
+    assert!(!client.is_curve_server().unwrap());
+    assert_eq!(
+        client.get_curve_serverkey().unwrap(),
+        server_pair.public_key
+    );
+
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = server.get_last_endpoint().unwrap().unwrap();
+    client.connect(&ep).unwrap();
+    client.send("foo", 0).unwrap();
+    assert_eq!(server.recv_bytes(0).unwrap(), b"foo");
+});
+
+test_capability!(test_curve_security_bad_key, "curve", {
+    let ctx = Context::new();
+    let sock = ctx.socket(zmq::REQ).unwrap();
+    let result = sock.set_security(Security::CurveClient {
+        server_key: vec![0; 31],
+        keypair: CurveKeyPair::new().unwrap(),
+    });
+    assert_eq!(result, Err(Error::EINVAL));
+    assert_eq!(sock.get_mechanism().unwrap(), Mechanism::ZMQ_NULL);
+});
+
+test_capability!(test_curve_security_inconsistent, "curve", {
+    let ctx = Context::new();
+    let sock = ctx.socket(zmq::REQ).unwrap();
+    sock.set_plain_server(true).unwrap();
+    let security = Security::CurveServer {
+        keypair: CurveKeyPair::new().unwrap(),
+        zap_domain: None,
+    };
+    assert_eq!(sock.set_security(security.clone()), Err(Error::EINVAL));
+    assert_eq!(sock.get_mechanism().unwrap(), Mechanism::ZMQ_PLAIN);
+
+    sock.set_security(Security::Null).unwrap();
+    assert_eq!(sock.get_mechanism().unwrap(), Mechanism::ZMQ_NULL);
+    sock.set_security(security).unwrap();
+    assert_eq!(sock.get_mechanism().unwrap(), Mechanism::ZMQ_CURVE);
+});
@@ -0,0 +1,199 @@
+#[macro_use]
+mod common;
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use zmq::{Context, Endpoint, EndpointError, Host, PgmAddr, Port, TcpAddr};
+
+#[test]
+fn test_endpoint_roundtrip() {
+    let endpoints = [
+        "tcp://127.0.0.1:5555",
+        "tcp://*:*",
+        "tcp://eth0:5555",
+        "tcp://[::1]:5555",
+        "tcp://192.168.1.17:0;example.com:5555",
+        "ipc:///tmp/feeds/0",
+        "ipc://@abstract",
+        "inproc://#1",
+        "pgm://eth0;239.192.1.1:5555",
+        "epgm://192.168.1.1;239.192.1.1:5555",
+        "tipc://{5560,0,0}",
+        "vmci://*:*",
+        "vmci://1:5555",
+        "ws://127.0.0.1:8080",
+        "wss://[::1]:443/feeds/0",
+    ];
+    for s in &endpoints {
+        let endpoint: Endpoint = s.parse().unwrap();
+        assert_eq!(&endpoint.to_string(), s);
+    }
+}
+
+#[test]
+fn test_endpoint_parse() {
+    assert_eq!(
+        "tcp://*:*".parse(),
+        Ok(Endpoint::Tcp {
+            source: None,
+            addr: TcpAddr::new(Host::Wildcard, Port::Ephemeral),
+        })
+    );
+    assert_eq!(
+        "tcp://10.0.0.1:4000;localhost:5555".parse(),
+        Ok(Endpoint::tcp_with_source(
+            TcpAddr::new(Ipv4Addr::new(10, 0, 0, 1), 4000),
+            TcpAddr::new(Host::Name("localhost".into()), 5555),
+        ))
+    );
+    assert_eq!(
+        "epgm://eth0;239.192.1.1:5555".parse(),
+        Ok(Endpoint::Epgm(PgmAddr {
+            interface: "eth0".into(),
+            group: Ipv4Addr::new(239, 192, 1, 1).into(),
+            port: 5555,
+        }))
+    );
+    assert_eq!(
+        "wss://example.com:443/feed".parse(),
+        Ok(Endpoint::Wss {
+            addr: TcpAddr::new("example.com", 443),
+            path: "/feed".into(),
+        })
+    );
+    assert_eq!(
+        "vmci://*:5555".parse(),
+        Ok(Endpoint::Vmci {
+            cid: None,
+            port: Some(5555),
+        })
+    );
+}
+
+#[test]
+fn test_endpoint_parse_errors() {
+    let invalid = [
+        ("127.0.0.1:5555", EndpointError::MissingTransport),
+        (
+            "udt://127.0.0.1:5555",
+            EndpointError::UnknownTransport("udt".into()),
+        ),
+        ("inproc://", EndpointError::EmptyAddress),
+        ("ipc://@", EndpointError::EmptyAddress),
+        ("tcp://127.0.0.1", EndpointError::MissingPort),
+        (
+            "tcp://127.0.0.1:65536",
+            EndpointError::InvalidPort("65536".into()),
+        ),
+        ("tcp://:5555", EndpointError::InvalidHost("".into())),
+        ("tcp://::1:5555", EndpointError::InvalidHost("::1".into())),
+        (
+            "tcp://[::g]:5555",
+            EndpointError::InvalidHost("[::g]".into()),
+        ),
+        (
+            "pgm://eth0;10.0.0.1:5555",
+            EndpointError::InvalidHost("10.0.0.1".into()),
+        ),
+        (
+            "pgm://eth0;239.192.1.1:*",
+            EndpointError::InvalidPort("*".into()),
+        ),
+        ("inproc://a\0b", EndpointError::NulByte),
+    ];
+    for &(s, ref error) in &invalid {
+        assert_eq!(s.parse::<Endpoint>().as_ref(), Err(error), "{}", s);
+    }
+}
+
+#[test]
+fn test_endpoint_constructors() {
+    assert_eq!(Endpoint::tcp("*", 5555).to_string(), "tcp://*:5555");
+    assert_eq!(
+        Endpoint::tcp(Ipv6Addr::LOCALHOST, Port::Ephemeral).to_string(),
+        "tcp://[::1]:*"
+    );
+    let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 5555);
+    assert_eq!(Endpoint::from(addr).to_string(), "tcp://127.0.0.1:5555");
+    assert_eq!(Endpoint::ipc_abstract("name").to_string(), "ipc://@name");
+    assert_eq!(Endpoint::inproc("name").transport(), "inproc");
+}
+
+test!(test_bind_connect_endpoint, {
+    let ctx = Context::new();
+    let receiver = ctx.socket(zmq::PULL).unwrap();
+    receiver
+        .bind(Endpoint::tcp(Ipv4Addr::LOCALHOST, Port::Ephemeral))
+        .unwrap();
+    let endpoint: Endpoint = receiver
+        .get_last_endpoint()
+        .unwrap()
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    let sender = ctx.socket(zmq::PUSH).unwrap();
+    sender.connect(&endpoint).unwrap();
+    sender.send("hello", 0).unwrap();
+    assert_eq!(receiver.recv_bytes(0).unwrap(), b"hello");
+    sender.disconnect(endpoint).unwrap();
+});
+
+test!(test_bind_nul_byte, {
+    let ctx = Context::new();
+    let sock = ctx.socket(zmq::PULL).unwrap();
+    assert_eq!(sock.bind("inproc://a\0b"), Err(zmq::Error::EINVAL));
+    assert_eq!(sock.connect("inproc://a\0b"), Err(zmq::Error::EINVAL));
+    assert!(sock.endpoints().is_empty());
+});
+
+test!(test_bind_ephemeral, {
+    let ctx = Context::new();
+    let receiver = ctx.socket(zmq::PULL).unwrap();
+    let addr = receiver.bind_ephemeral("127.0.0.1").unwrap();
+    assert_eq!(addr.ip(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+    assert_ne!(addr.port(), 0);
+    assert_eq!(
+        receiver.get_last_endpoint().unwrap().unwrap(),
+        format!("tcp://{}", addr)
+    );
+
+    // The unspecified address is replaced by the loopback address.
+    let wildcard = ctx.socket(zmq::PULL).unwrap();
+    let wildcard_addr = wildcard.bind_ephemeral(Host::Wildcard).unwrap();
+    assert_eq!(wildcard_addr.ip(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+    let sender = ctx.socket(zmq::PUSH).unwrap();
+    sender.connect(addr).unwrap();
+    sender.connect(wildcard_addr).unwrap();
+    sender.send("hello", 0).unwrap();
+    sender.send("hello", 0).unwrap();
+    assert_eq!(receiver.recv_bytes(0).unwrap(), b"hello");
+    assert_eq!(wildcard.recv_bytes(0).unwrap(), b"hello");
+});
+
+test!(test_bind_in_range, {
+    let ctx = Context::new();
+    let first = ctx.socket(zmq::PULL).unwrap();
+    let addr = first.bind_ephemeral("127.0.0.1").unwrap();
+    let port = addr.port();
+    if port == 65535 {
+        return;
+    }
+
+    // The port in use is skipped.
+    let second = ctx.socket(zmq::PULL).unwrap();
+    let next = second.bind_in_range("127.0.0.1", port..=port + 1);
+    match next {
+        Ok(next) => assert_eq!(next.port(), port + 1),
+        Err(e) => assert_eq!(e, zmq::Error::EADDRINUSE),
+    }
+    assert_eq!(
+        second.bind_in_range("127.0.0.1", port..=port),
+        Err(zmq::Error::EADDRINUSE)
+    );
+    assert_eq!(
+        second.bind_in_range("127.0.0.1", port + 1..=port),
+        Err(zmq::Error::EINVAL)
+    );
+});
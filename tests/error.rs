@@ -6,3 +6,76 @@ fn from_raw_eintr() {
     let error = Error::from_raw(errno::EINTR);
     assert_eq!(error, Error::EINTR);
 }
+
+#[test]
+fn from_raw_unknown() {
+    let error = Error::from_raw(errno::ETIMEDOUT_ALT);
+    assert_eq!(error, Error::Other(errno::ETIMEDOUT_ALT));
+    assert_eq!(error.to_raw(), errno::ETIMEDOUT_ALT);
+    assert!(!error.message().is_empty());
+
+    assert_eq!(Error::from_raw(errno::ENOTCONN_ALT), Error::ENOTCONN);
+}
+
+#[test]
+fn io_error_kind() {
+    use std::io::{self, ErrorKind};
+
+    let kind = |error: Error| io::Error::from(error).kind();
+    assert_eq!(kind(Error::EAGAIN), ErrorKind::WouldBlock);
+    assert_eq!(kind(Error::ECONNREFUSED), ErrorKind::ConnectionRefused);
+    assert_eq!(
+        kind(Error::Other(errno::ETIMEDOUT_ALT)),
+        ErrorKind::TimedOut
+    );
+    assert_eq!(kind(Error::ETERM), ErrorKind::Other);
+    #[cfg(unix)]
+    assert_eq!(
+        kind(Error::from_raw(nix::errno::Errno::ETIMEDOUT as i32)),
+        ErrorKind::TimedOut
+    );
+}
+
+#[test]
+fn operation_error() {
+    let ctx = Context::new();
+    let first = ctx.socket(PULL).unwrap();
+    first.bind("inproc://operation_error").unwrap();
+
+    let endpoint = "inproc://operation_error";
+    let second = ctx.socket(PULL).unwrap();
+    assert_eq!(second.bind(endpoint), Err(Error::EADDRINUSE));
+    let error = second.bind_with_operation(endpoint).unwrap_err();
+    assert_eq!(error.error(), Error::EADDRINUSE);
+    assert_eq!(error.operation(), &Operation::Bind(endpoint.into()));
+    assert_eq!(
+        error.to_string(),
+        format!("failed to bind to {}: {}", endpoint, Error::EADDRINUSE)
+    );
+
+    let io_error = std::io::Error::from(error.clone());
+    assert_eq!(io_error.kind(), std::io::ErrorKind::AddrInUse);
+    assert_eq!(io_error.to_string(), error.to_string());
+
+    let error = second
+        .connect_with_operation("bogus://operation_error")
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "failed to connect to bogus://operation_error: {}",
+            Error::EPROTONOSUPPORT
+        )
+    );
+    let error = second.unbind_with_operation(endpoint).unwrap_err();
+    assert_eq!(error.operation(), &Operation::Unbind(endpoint.into()));
+    let error = second.disconnect_with_operation(endpoint).unwrap_err();
+    assert_eq!(error.operation(), &Operation::Disconnect(endpoint.into()));
+    assert!(second.endpoints().is_empty());
+
+    let error = Error::EINVAL.with_operation(Operation::SetSockOpt("ZMQ_SNDHWM"));
+    assert_eq!(
+        error.to_string(),
+        format!("failed to set socket option ZMQ_SNDHWM: {}", Error::EINVAL)
+    );
+}
@@ -0,0 +1,127 @@
+#[macro_use]
+mod common;
+
+use zmq::{Context, Empty, FrameError, FromFrames, Message, ToFrames};
+
+#[test]
+fn test_frames_roundtrip() {
+    let value = (
+        b"id".to_vec(),
+        Empty,
+        "body".to_string(),
+        0x0102_0304_u32,
+        -2_i8,
+    );
+    let frames = value.encode();
+    assert_eq!(frames.len(), 5);
+    assert_eq!(&frames[3][..], &[1, 2, 3, 4]);
+    assert_eq!(&frames[4][..], &[0xfe]);
+    assert_eq!(FromFrames::decode(frames), Ok(value));
+}
+
+#[test]
+fn test_frames_decode_errors() {
+    let frames = |parts: &[&[u8]]| -> Vec<Message> { parts.iter().map(|&p| p.into()).collect() };
+
+    assert_eq!(
+        <(String, String)>::decode(frames(&[b"a"])),
+        Err(FrameError::MissingFrame)
+    );
+    assert_eq!(
+        String::decode(frames(&[b"a", b"b", b"c"])),
+        Err(FrameError::TrailingFrames(2))
+    );
+    assert_eq!(
+        u16::decode(frames(&[b"abc"])),
+        Err(FrameError::InvalidLength {
+            expected: 2,
+            actual: 3
+        })
+    );
+    assert_eq!(Empty::decode(frames(&[b"a"])), Err(FrameError::NotEmpty));
+    match String::decode(frames(&[b"\xff"])) {
+        Err(FrameError::InvalidUtf8(_)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+test!(test_send_recv_typed, {
+    let ctx = Context::new();
+    let router = ctx.socket(zmq::ROUTER).unwrap();
+    let dealer = ctx.socket(zmq::DEALER).unwrap();
+    dealer.set_identity(b"dealer").unwrap();
+    router.bind("inproc://typed").unwrap();
+    dealer.connect("inproc://typed").unwrap();
+
+    dealer.send_typed(&(Empty, "hello"), 0).unwrap();
+    let (id, Empty, body): (Vec<u8>, Empty, String) = router.recv_typed(0).unwrap().unwrap();
+    assert_eq!(id, b"dealer");
+    assert_eq!(body, "hello");
+
+    router.send_typed(&(&id[..], Empty, 42_u64), 0).unwrap();
+    assert_eq!(dealer.recv_typed(0).unwrap(), Ok((Empty, 42_u64)));
+
+    // The whole message is received even if it fails to decode
+    dealer.send_typed(&(Empty, "a", "b"), 0).unwrap();
+    let result = router.recv_typed::<(Vec<u8>, Empty, String), _>(0).unwrap();
+    assert_eq!(result, Err(FrameError::TrailingFrames(1)));
+    assert!(!router.get_rcvmore().unwrap());
+
+    router.unbind("inproc://typed").unwrap();
+});
+
+#[cfg(any(feature = "json", feature = "msgpack", feature = "bincode"))]
+mod serde {
+    use std::collections::BTreeMap;
+    use zmq::{Codec, Context, SerdeError, Socket};
+
+    fn socket_pair(ctx: &Context, endpoint: &str) -> (Socket, Socket) {
+        let sender = ctx.socket(zmq::PAIR).unwrap();
+        let receiver = ctx.socket(zmq::PAIR).unwrap();
+        receiver.bind(endpoint).unwrap();
+        sender.connect(endpoint).unwrap();
+        (sender, receiver)
+    }
+
+    fn codecs() -> Vec<Codec> {
+        vec![
+            #[cfg(feature = "json")]
+            Codec::Json,
+            #[cfg(feature = "msgpack")]
+            Codec::MessagePack,
+            #[cfg(feature = "bincode")]
+            Codec::Bincode,
+        ]
+    }
+
+    test!(test_send_recv_serde, {
+        let ctx = Context::new();
+        let (sender, receiver) = socket_pair(&ctx, "inproc://serde");
+
+        let mut value = BTreeMap::new();
+        value.insert("answer".to_string(), (42_u32, vec![1_u8, 2, 3]));
+        for codec in codecs() {
+            sender.send_serde(&value, codec, 0).unwrap();
+            let received: BTreeMap<String, (u32, Vec<u8>)> = receiver.recv_serde(codec, 0).unwrap();
+            assert_eq!(received, value);
+        }
+
+        receiver.unbind("inproc://serde").unwrap();
+    });
+
+    test!(test_recv_serde_decode_error, {
+        let ctx = Context::new();
+        let (sender, receiver) = socket_pair(&ctx, "inproc://serde_error");
+
+        for codec in codecs() {
+            // Too short for a `u64` in any of the codecs
+            sender.send(&[0x92][..], 0).unwrap();
+            match receiver.recv_serde::<(u64, u64), _>(codec, 0) {
+                Err(SerdeError::Decode { frame, .. }) => assert_eq!(&frame[..], &[0x92]),
+                other => panic!("unexpected result for {:?}: {:?}", codec, other),
+            }
+        }
+
+        receiver.unbind("inproc://serde_error").unwrap();
+    });
+}
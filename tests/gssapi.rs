@@ -33,3 +33,23 @@ test_capability!(test_getset_gssapi_plaintext, "gssapi", {
     sock.set_gssapi_plaintext(true).unwrap();
     assert_eq!(sock.is_gssapi_plaintext().unwrap(), true);
 });
+
+test_capability!(test_getset_gssapi_principal_nametype, "gssapi", {
+    let ctx = Context::new();
+    let sock = ctx.socket(zmq::REQ).unwrap();
+    let nametype = zmq_sys::ZMQ_GSSAPI_NT_USER_NAME as i32;
+    sock.set_gssapi_principal_nametype(nametype).unwrap();
+    assert_eq!(sock.get_gssapi_principal_nametype().unwrap(), nametype);
+});
+
+test_capability!(test_getset_gssapi_service_principal_nametype, "gssapi", {
+    let ctx = Context::new();
+    let sock = ctx.socket(zmq::REQ).unwrap();
+    let nametype = zmq_sys::ZMQ_GSSAPI_NT_KRB5_PRINCIPAL as i32;
+    sock.set_gssapi_service_principal_nametype(nametype)
+        .unwrap();
+    assert_eq!(
+        sock.get_gssapi_service_principal_nametype().unwrap(),
+        nametype
+    );
+});
@@ -33,3 +33,282 @@ quickcheck! {
         Message::from(input) == original
     }
 }
+
+#[test]
+fn test_msg_from_arc() {
+    use std::sync::Arc;
+
+    let data: Arc<[u8]> = Arc::from(&b"shared payload"[..]);
+    let ctx = zmq::Context::new();
+    let sender = ctx.socket(zmq::PUSH).unwrap();
+    let receiver = ctx.socket(zmq::PULL).unwrap();
+    receiver.bind("inproc://msg_from_arc").unwrap();
+    sender.connect("inproc://msg_from_arc").unwrap();
+
+    for _ in 0..3 {
+        sender.send(&data, 0).unwrap();
+    }
+    for _ in 0..3 {
+        assert_eq!(receiver.recv_bytes(0).unwrap(), &data[..]);
+    }
+    // All references held by messages have been released.
+    assert_eq!(Arc::strong_count(&data), 1);
+
+    // Mutable access copies the content, leaving the shared data alone.
+    let mut msg = Message::from(data.clone());
+    msg[0] = b'S';
+    assert_eq!(&msg[..], b"Shared payload");
+    assert_eq!(&data[..], b"shared payload");
+    assert_eq!(Arc::strong_count(&data), 1);
+
+    assert!(Message::from(Arc::<[u8]>::from(Vec::new())).is_empty());
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn test_msg_bytes() {
+    use bytes::Bytes;
+
+    let data = Bytes::from_static(b"shared payload");
+    let msg = Message::from(data.clone());
+    assert_eq!(&msg[..], &data[..]);
+    // The message references the data instead of copying it.
+    assert_eq!(msg.as_ptr(), data.as_ptr());
+
+    // Mutable access copies the content, leaving the shared data alone.
+    let mut copy = Message::from(data.clone());
+    copy[0] = b'S';
+    assert_eq!(&copy[..], b"Shared payload");
+    assert_eq!(&data[..], b"shared payload");
+
+    assert_eq!(msg.to_bytes(), data);
+    let ptr = msg.as_ptr();
+    let bytes = msg.into_bytes();
+    assert_eq!(bytes, data);
+    assert_eq!(bytes.as_ptr(), ptr);
+
+    // Small messages store their data inline, which must not move.
+    let small = Message::from("x").into_bytes();
+    assert_eq!(&small[..], b"x");
+}
+
+#[test]
+fn test_msg_from_static() {
+    static HEADER: &[u8] = b"header";
+
+    let msg = Message::from_static(HEADER);
+    assert_eq!(&msg[..], HEADER);
+    assert_eq!(msg.as_ptr(), HEADER.as_ptr());
+
+    // Mutable access copies the content first.
+    let mut msg = msg;
+    msg[0] = b'H';
+    assert_eq!(&msg[..], b"Header");
+    assert_eq!(HEADER, b"header");
+}
+
+#[test]
+fn test_send_static() {
+    let ctx = zmq::Context::new();
+    let sender = ctx.socket(zmq::PUSH).unwrap();
+    let receiver = ctx.socket(zmq::PULL).unwrap();
+    receiver.bind("inproc://send_static").unwrap();
+    sender.connect("inproc://send_static").unwrap();
+
+    sender.send_static(b"header", zmq::SNDMORE).unwrap();
+    sender.send_static(b"", zmq::SNDMORE).unwrap();
+    sender.send(Message::from_static(b"body"), 0).unwrap();
+    assert_eq!(
+        receiver.recv_multipart(0).unwrap(),
+        vec![b"header".to_vec(), vec![], b"body".to_vec()]
+    );
+
+    // Receiving replaces the static content, which can then be modified
+    // in place.
+    let mut msg = Message::from_static(b"static");
+    sender.send("received", 0).unwrap();
+    receiver.recv(&mut msg, 0).unwrap();
+    let ptr = msg.as_ptr();
+    msg[0] = b'R';
+    assert_eq!(&msg[..], b"Received");
+    assert_eq!(msg.as_ptr(), ptr);
+}
+
+#[test]
+fn test_recv_shared_content() {
+    use std::sync::Arc;
+
+    let ctx = zmq::Context::new();
+    let sender = ctx.socket(zmq::PAIR).unwrap();
+    let receiver = ctx.socket(zmq::PAIR).unwrap();
+    receiver.bind("inproc://recv_shared_content").unwrap();
+    sender.connect("inproc://recv_shared_content").unwrap();
+
+    // Over inproc, the content of sent messages is passed on as it is, so
+    // modifying a received message must not write through to the source.
+    static STATIC: &[u8] = b"static data, large enough not to be stored inline";
+    let data: Arc<[u8]> = Arc::from(vec![b'a'; 100]);
+    let mut original = Message::from(vec![b'o'; 100]);
+    sender.send(Message::from(data.clone()), 0).unwrap();
+    sender.send(Message::from_static(STATIC), 0).unwrap();
+    sender.send(original.share(), 0).unwrap();
+
+    let mut msg = receiver.recv_msg(0).unwrap();
+    msg[0] = b'Z';
+    assert_eq!(msg[0], b'Z');
+    assert_eq!(&data[..], &[b'a'; 100][..]);
+
+    let mut msg = receiver.recv_msg(0).unwrap();
+    msg[0] = b'Z';
+    assert_eq!(&msg[1..], &STATIC[1..]);
+    assert_eq!(STATIC[0], b's');
+
+    let mut msg = receiver.recv_msg(0).unwrap();
+    msg[0] = b'Z';
+    assert_eq!(&original[..], &[b'o'; 100][..]);
+}
+
+#[test]
+fn test_recv_mutate_in_place() {
+    let ctx = zmq::Context::new();
+    let sender = ctx.socket(zmq::DEALER).unwrap();
+    let receiver = ctx.socket(zmq::DEALER).unwrap();
+    receiver.bind("tcp://127.0.0.1:*").unwrap();
+    let endpoint = receiver.get_last_endpoint().unwrap().unwrap();
+    sender.connect(&endpoint).unwrap();
+
+    // Received messages are modified in place, keeping their properties.
+    sender
+        .send_multipart(vec![vec![b'a'; 100], vec![b'b'; 100]], 0)
+        .unwrap();
+    let mut parts = Vec::new();
+    receiver.recv_multipart_into(&mut parts, 0).unwrap();
+    let ptr = parts[0].as_ptr();
+    parts[0][0] = b'Z';
+    assert_eq!(parts[0].as_ptr(), ptr);
+    assert_eq!(parts[0][0], b'Z');
+    assert!(parts[0].get_more());
+    assert_eq!(parts[0].peer_address(), Some("127.0.0.1".parse().unwrap()));
+}
+
+#[test]
+fn test_msg_share() {
+    let ctx = zmq::Context::new();
+    let sender = ctx.socket(zmq::PUSH).unwrap();
+    let receiver = ctx.socket(zmq::PULL).unwrap();
+    receiver.bind("inproc://msg_share").unwrap();
+    sender.connect("inproc://msg_share").unwrap();
+
+    let payload = vec![42u8; 1024];
+    sender.send(&payload, 0).unwrap();
+    let mut msg = receiver.recv_msg(0).unwrap();
+    let shares: Vec<Message> = (0..3).map(|_| msg.share()).collect();
+    for share in &shares {
+        assert_eq!(share.as_ptr(), msg.as_ptr());
+    }
+
+    // The shared content outlives the sockets and the context.
+    drop(sender);
+    drop(receiver);
+    drop(ctx);
+    drop(msg);
+    for share in &shares {
+        assert_eq!(&share[..], &payload[..]);
+    }
+
+    // Modifying a shared message copies it.
+    let mut shares = shares;
+    shares[0][0] = 0;
+    assert_eq!(shares[0][0], 0);
+    assert_eq!(shares[1][0], 42);
+    assert_ne!(shares[0].as_ptr(), shares[1].as_ptr());
+}
+
+#[test]
+fn test_msg_share_drop() {
+    use std::sync::Arc;
+
+    let data: Arc<[u8]> = Arc::from(vec![1u8; 100]);
+    let mut msg = Message::from(data.clone());
+    let share = msg.share();
+    assert_eq!(Arc::strong_count(&data), 2);
+    drop(msg);
+    assert_eq!(&share[..], &data[..]);
+    assert_eq!(Arc::strong_count(&data), 2);
+    drop(share);
+    assert_eq!(Arc::strong_count(&data), 1);
+}
+
+#[test]
+fn test_msg_take() {
+    let mut msg = Message::from(vec![7u8; 100]);
+    let ptr = msg.as_ptr();
+    let taken = msg.take();
+    assert!(msg.is_empty());
+    assert_eq!(&taken[..], &[7u8; 100][..]);
+    assert_eq!(taken.as_ptr(), ptr);
+
+    // Taken messages stay read-only if their content is shared.
+    let mut taken = Message::from_static(b"static").take();
+    taken[0] = b'S';
+    assert_eq!(&taken[..], b"Static");
+}
+
+#[test]
+fn test_message_pool() {
+    let pool = zmq::MessagePool::new(64, 1);
+    let mut msg = pool.copy_from(&[b'h'; 40]);
+    assert_eq!(&msg[..], &[b'h'; 40][..]);
+    msg[0] = b'j';
+    assert_eq!(msg[0], b'j');
+    drop(msg);
+
+    // The buffer is reused, and zeroed for `message`
+    let msg = pool.message(48);
+    assert_eq!(&msg[..], &[0; 48][..]);
+    let stats = pool.stats();
+    assert_eq!((stats.hits, stats.misses, stats.returned), (1, 1, 1));
+    assert_eq!(stats.available, 0);
+
+    // Only `capacity` buffers are kept
+    let other = pool.message(48);
+    drop(msg);
+    drop(other);
+    let stats = pool.stats();
+    assert_eq!((stats.returned, stats.discarded), (2, 1));
+    assert_eq!(stats.available, 1);
+
+    let large = pool.copy_from(&[1; 100]);
+    assert_eq!(&large[..], &[1; 100][..]);
+    assert_eq!(pool.stats().oversized, 1);
+
+    // Small messages are stored inline by 0MQ, bypassing the pool
+    let small = pool.copy_from(b"hello");
+    assert_eq!(&small[..], b"hello");
+    let stats = pool.stats();
+    assert_eq!((stats.hits, stats.misses, stats.oversized), (1, 2, 1));
+    assert_eq!(stats.available, 1);
+}
+
+#[test]
+fn test_message_pool_send() {
+    let ctx = zmq::Context::new();
+    let sender = ctx.socket(zmq::PAIR).unwrap();
+    let receiver = ctx.socket(zmq::PAIR).unwrap();
+    receiver.bind("inproc://message_pool").unwrap();
+    sender.connect("inproc://message_pool").unwrap();
+
+    let pool = zmq::MessagePool::new(64, 4);
+    pool.reserve(2);
+    assert_eq!(pool.stats().available, 2);
+    for i in 0..10 {
+        let payload = [i as u8; 40];
+        sender.send(pool.copy_from(&payload), 0).unwrap();
+        assert_eq!(receiver.recv_bytes(0).unwrap(), &payload[..]);
+    }
+    let stats = pool.stats();
+    assert_eq!(stats.hits, 10);
+    assert_eq!(stats.misses, 0);
+
+    receiver.unbind("inproc://message_pool").unwrap();
+}
@@ -23,7 +23,7 @@ fn get_monitor_event(monitor: &mut zmq::Socket) -> zmq::Result<zmq::SocketEvent>
     // the address, we'll ignore it
     let _ = monitor.recv_msg(0)?;
 
-    Ok(zmq::SocketEvent::from_raw(event))
+    zmq::SocketEvent::try_from_raw(event).ok_or(zmq::Error::EINVAL)
 }
 
 fn expect_event(mon: &mut zmq::Socket, expected: zmq::SocketEvent) {
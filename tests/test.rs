@@ -3,6 +3,7 @@ mod common;
 
 use std::io;
 use std::net::TcpStream;
+use std::time::{Duration, Instant};
 use zmq::*;
 
 fn version_ge_4_2() -> bool {
@@ -10,6 +11,11 @@ fn version_ge_4_2() -> bool {
     (major > 4) || (major == 4 && minor >= 2)
 }
 
+fn version_ge_4_3() -> bool {
+    let (major, minor, _) = version();
+    (major > 4) || (major == 4 && minor >= 3)
+}
+
 fn create_socketpair() -> (Socket, Socket) {
     let ctx = Context::default();
 
@@ -52,8 +58,16 @@ test!(test_exchanging_bytes, {
 
     receiver.send("a quite long string", 0).unwrap();
     let mut buf = [0_u8; 10];
-    sender.recv_into(&mut buf, 0).unwrap(); // this should truncate the message
+    let result = sender.recv_into(&mut buf, 0).unwrap(); // this should truncate the message
     assert_eq!(&buf[..], b"a quite lo");
+    assert_eq!(result.received, 10);
+    assert_eq!(result.full_len, 19);
+    assert!(result.is_truncated());
+
+    sender.send("baz", 0).unwrap();
+    let result = receiver.recv_into(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..result.received], b"baz");
+    assert!(!result.is_truncated());
 });
 
 test!(test_exchanging_strings, {
@@ -87,6 +101,59 @@ test!(test_exchanging_multipart, {
     assert_eq!(&msg2[..], b"bar");
 });
 
+test!(test_exchanging_multipart_buffers, {
+    use std::io::{IoSlice, IoSliceMut};
+
+    let (sender, receiver) = create_socketpair();
+
+    let header = b"header";
+    let body = vec![1_u8; 100];
+    sender
+        .send_multipart_buffers(&[IoSlice::new(header), IoSlice::new(&body)], 0)
+        .unwrap();
+    let (mut buf1, mut buf2) = ([0_u8; 6], [0_u8; 10]);
+    let results = receiver
+        .recv_multipart_buffers(
+            &mut [IoSliceMut::new(&mut buf1), IoSliceMut::new(&mut buf2)],
+            0,
+        )
+        .unwrap();
+    assert!(!results[0].is_truncated());
+    assert!(results[1].is_truncated());
+    assert_eq!(results[1].full_len, 100);
+    assert_eq!(&buf1, header);
+    assert_eq!(&buf2, &body[..10]);
+
+    // Frames without a buffer are discarded
+    receiver
+        .send_multipart_buffers(&[&b"foo"[..], b"bar", b"baz"], 0)
+        .unwrap();
+    let mut buf = [0_u8; 3];
+    let results = sender
+        .recv_multipart_buffers(&mut [&mut buf[..]], 0)
+        .unwrap();
+    let received: Vec<_> = results.iter().map(|r| r.received).collect();
+    assert_eq!(received, vec![3, 0, 0]);
+    assert_eq!(&buf[..], b"foo");
+    assert!(!sender.get_rcvmore().unwrap());
+});
+
+test!(test_recv_multipart_into, {
+    let (sender, receiver) = create_socketpair();
+    let mut parts = vec![];
+
+    sender.send_multipart(["foo", "bar", "baz"], 0).unwrap();
+    receiver.recv_multipart_into(&mut parts, 0).unwrap();
+    assert_eq!(parts.len(), 3);
+    assert_eq!(&parts[2][..], b"baz");
+
+    // Frames left over from the longer message are dropped
+    receiver.send_multipart(["qux", "quux"], 0).unwrap();
+    sender.recv_multipart_into(&mut parts, 0).unwrap();
+    let parts: Vec<_> = parts.iter().map(|part| &part[..]).collect();
+    assert_eq!(parts, vec![&b"qux"[..], b"quux"]);
+});
+
 test!(test_polling, {
     let (sender, receiver) = create_socketpair();
 
@@ -473,6 +540,296 @@ test!(test_getset_plain_password, {
     assert!(sock.get_mechanism().unwrap() == Mechanism::ZMQ_NULL);
 });
 
+// Answer a single ZAP request with the given status code, user id and
+// metadata.
+fn spawn_zap_handler(
+    ctx: &Context,
+    status_code: &'static str,
+    user_id: &'static str,
+    metadata: Vec<u8>,
+) -> std::thread::JoinHandle<()> {
+    let handler = ctx.socket(REP).unwrap();
+    handler.bind("inproc://zeromq.zap.01").unwrap();
+    std::thread::spawn(move || {
+        let request = handler.recv_multipart(0).unwrap();
+        let (version, request_id) = (&request[0], &request[1]);
+        handler
+            .send_multipart(
+                [
+                    &version[..],
+                    &request_id[..],
+                    status_code.as_bytes(),
+                    b"",
+                    user_id.as_bytes(),
+                    &metadata[..],
+                ],
+                0,
+            )
+            .unwrap();
+    })
+}
+
+test!(test_plain_security, {
+    let ctx = Context::new();
+    let zap_handler = spawn_zap_handler(&ctx, "200", "", vec![]);
+    let server = ctx.socket(REP).unwrap();
+    let client = ctx.socket(REQ).unwrap();
+
+    server
+        .set_security(Security::PlainServer { zap_domain: None })
+        .unwrap();
+    assert_eq!(server.get_mechanism().unwrap(), Mechanism::ZMQ_PLAIN);
+    assert!(server.is_plain_server().unwrap());
+
+    client
+        .set_security(Security::PlainClient {
+            username: "billybob".into(),
+            password: "m00c0w".into(),
+        })
+        .unwrap();
+    assert_eq!(client.get_mechanism().unwrap(), Mechanism::ZMQ_PLAIN);
+    assert!(!client.is_plain_server().unwrap());
+    assert_eq!(client.get_plain_username().unwrap().unwrap(), "billybob");
+    assert_eq!(client.get_plain_password().unwrap().unwrap(), "m00c0w");
+
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = server.get_last_endpoint().unwrap().unwrap();
+    client.connect(&ep).unwrap();
+    client.send("foo", 0).unwrap();
+    assert_eq!(server.recv_bytes(0).unwrap(), b"foo");
+    zap_handler.join().unwrap();
+
+    client.set_security(Security::Null).unwrap();
+    assert_eq!(client.get_mechanism().unwrap(), Mechanism::ZMQ_NULL);
+});
+
+test!(test_zap_metadata, {
+    // ZMTP property encoding: name length, name, value length, value.
+    let mut metadata = vec![5];
+    metadata.extend_from_slice(b"X-Foo");
+    metadata.extend_from_slice(&[0, 0, 0, 4]);
+    metadata.extend_from_slice(b"ba\xffr");
+
+    let ctx = Context::new();
+    let zap_handler = spawn_zap_handler(&ctx, "200", "billybob", metadata);
+    let server = ctx.socket(PULL).unwrap();
+    let client = ctx.socket(PUSH).unwrap();
+    server
+        .set_security(Security::PlainServer { zap_domain: None })
+        .unwrap();
+    client
+        .set_security(Security::PlainClient {
+            username: "billybob".into(),
+            password: "m00c0w".into(),
+        })
+        .unwrap();
+
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = server.get_last_endpoint().unwrap().unwrap();
+    client.connect(&ep).unwrap();
+    client.send("foo", 0).unwrap();
+    let msg = server.recv_msg(0).unwrap();
+    zap_handler.join().unwrap();
+
+    assert_eq!(msg.user_id(), Some(&b"billybob"[..]));
+    assert_eq!(msg.metadata("X-Foo"), Some(&b"ba\xffr"[..]));
+    assert_eq!(msg.metadata("Socket-Type"), Some(&b"PUSH"[..]));
+    assert_eq!(msg.metadata("X-Missing"), None);
+    assert_eq!(msg.metadata("X-\0"), None);
+});
+
+test!(test_message_properties, {
+    let ctx = Context::new();
+    let server = ctx.socket(ROUTER).unwrap();
+    let client = ctx.socket(DEALER).unwrap();
+    client.set_identity(b"client").unwrap();
+
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = server.get_last_endpoint().unwrap().unwrap();
+    client.connect(&ep).unwrap();
+    client.send("foo", 0).unwrap();
+    let mut id = server.recv_msg(0).unwrap();
+    let msg = server.recv_msg(0).unwrap();
+
+    assert_eq!(msg.peer_address(), Some("127.0.0.1".parse().unwrap()));
+    assert_eq!(msg.socket_type(), Some(DEALER));
+    assert_eq!(msg.routing_id(), Some(&b"client"[..]));
+
+    assert!(id.get_flag(MessageFlag::More).unwrap());
+    assert!(!msg.get_flag(MessageFlag::More).unwrap());
+    assert_eq!(id.set_flag(MessageFlag::More, false), Err(Error::EINVAL));
+    // Slice methods remain reachable via `Deref`
+    assert_eq!(msg.get(1), Some(&b'o'));
+    assert_eq!(msg.get(..2), Some(&b"fo"[..]));
+
+    // Only content too large to be stored inline is shared
+    let mut large = Message::from(vec![0; 100]);
+    assert!(!large.get_flag(MessageFlag::Shared).unwrap());
+    let _copy = large.share();
+    assert!(large.get_flag(MessageFlag::Shared).unwrap());
+    // Data shared outside of 0MQ is marked as shared as well
+    let data: std::sync::Arc<[u8]> = vec![0; 100].into();
+    assert!(Message::from(data).get_flag(MessageFlag::Shared).unwrap());
+    // Received messages own their content
+    assert!(!msg.get_flag(MessageFlag::Shared).unwrap());
+
+    // Messages created locally carry no metadata
+    let local = Message::from("foo");
+    assert_eq!(local.peer_address(), None);
+    assert_eq!(local.socket_type(), None);
+});
+
+test!(test_add_metadata, {
+    let ctx = Context::new();
+    let server = ctx.socket(PULL).unwrap();
+    let client = ctx.socket(PUSH).unwrap();
+
+    for &(key, value) in &[
+        ("Version", "1.0"),
+        ("X-Key:", "1.0"),
+        ("X-Version", ""),
+        ("X-Version", "1\0"),
+    ] {
+        assert_eq!(client.add_metadata(key, value), Err(Error::EINVAL));
+    }
+    // `ZMQ_METADATA` was added to the draft API of libzmq 4.3, so builds
+    // without it may reject the option.
+    match client.add_metadata("X-Version", "1.0") {
+        Ok(()) => {}
+        Err(Error::EINVAL) if !version_ge_4_3() || has("draft") != Some(true) => return,
+        Err(e) => panic!("add_metadata failed: {}", e),
+    }
+    client.add_metadata("X-Instance", "a:1").unwrap();
+
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = server.get_last_endpoint().unwrap().unwrap();
+    client.connect(&ep).unwrap();
+    client.send("foo", 0).unwrap();
+    let msg = server.recv_msg(0).unwrap();
+    assert_eq!(msg.metadata("X-Version"), Some(&b"1.0"[..]));
+    assert_eq!(msg.metadata("X-Instance"), Some(&b"a:1"[..]));
+});
+
+test!(test_zap_enforce_domain, {
+    if version_ge_4_3() {
+        let ctx = Context::new();
+        let sock = ctx.socket(REP).unwrap();
+        assert!(!sock.is_zap_enforce_domain().unwrap());
+        sock.set_zap_enforce_domain(true).unwrap();
+        assert!(sock.is_zap_enforce_domain().unwrap());
+    }
+});
+
+test!(test_plain_security_bad_username, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+    let result = sock.set_security(Security::PlainClient {
+        username: String::new(),
+        password: "m00c0w".into(),
+    });
+    assert_eq!(result, Err(Error::EINVAL));
+    assert_eq!(sock.get_mechanism().unwrap(), Mechanism::ZMQ_NULL);
+});
+
+test!(test_connect_and_handshake, {
+    let ctx = Context::new();
+    let zap_handler = spawn_zap_handler(&ctx, "200", "", vec![]);
+    let server = ctx.socket(REP).unwrap();
+    let client = ctx.socket(REQ).unwrap();
+    server
+        .set_security(Security::PlainServer { zap_domain: None })
+        .unwrap();
+    client
+        .set_security(Security::PlainClient {
+            username: "billybob".into(),
+            password: "m00c0w".into(),
+        })
+        .unwrap();
+
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = server.get_last_endpoint().unwrap().unwrap();
+    assert_eq!(
+        client.connect_and_handshake(&ep, Duration::from_secs(5)),
+        Ok(Ok(()))
+    );
+    zap_handler.join().unwrap();
+    client.send("foo", 0).unwrap();
+    assert_eq!(server.recv_bytes(0).unwrap(), b"foo");
+});
+
+test!(test_connect_and_handshake_denied, {
+    let ctx = Context::new();
+    let zap_handler = spawn_zap_handler(&ctx, "400", "", vec![]);
+    let server = ctx.socket(REP).unwrap();
+    let client = ctx.socket(REQ).unwrap();
+    server
+        .set_security(Security::PlainServer { zap_domain: None })
+        .unwrap();
+    client
+        .set_security(Security::PlainClient {
+            username: "billybob".into(),
+            password: "wrong".into(),
+        })
+        .unwrap();
+
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = server.get_last_endpoint().unwrap().unwrap();
+    let failure = client
+        .connect_and_handshake(&ep, Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(failure, Err(HandshakeFailure::Auth(400)));
+    zap_handler.join().unwrap();
+});
+
+test!(test_connect_and_handshake_mismatch, {
+    let ctx = Context::new();
+    let server = ctx.socket(REP).unwrap();
+    let client = ctx.socket(REQ).unwrap();
+    server
+        .set_security(Security::PlainServer { zap_domain: None })
+        .unwrap();
+
+    server.bind("tcp://127.0.0.1:*").unwrap();
+    let ep = server.get_last_endpoint().unwrap().unwrap();
+    let failure = client
+        .connect_and_handshake(&ep, Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(failure, Err(HandshakeFailure::ZmtpMechanismMismatch));
+});
+
+test!(test_connect_and_handshake_timeout, {
+    let ctx = Context::new();
+    let client = ctx.socket(REQ).unwrap();
+    assert_eq!(
+        client.connect_and_handshake("tcp://127.0.0.1:1", Duration::from_millis(100)),
+        Err(Error::EAGAIN)
+    );
+});
+
+test!(test_handshake_failure_from_event, {
+    assert_eq!(
+        HandshakeFailure::from_event(
+            SocketEvent::HANDSHAKE_FAILED_PROTOCOL,
+            zmq_sys::ZMQ_PROTOCOL_ERROR_ZMTP_CRYPTOGRAPHIC
+        ),
+        Some(HandshakeFailure::ZmtpCryptographic)
+    );
+    assert_eq!(
+        HandshakeFailure::from_event(SocketEvent::HANDSHAKE_FAILED_PROTOCOL, 0x1234),
+        Some(HandshakeFailure::Protocol(0x1234))
+    );
+    assert_eq!(
+        HandshakeFailure::from_event(SocketEvent::HANDSHAKE_FAILED_AUTH, 400)
+            .unwrap()
+            .to_string(),
+        "authentication failed with ZAP status 400"
+    );
+    assert_eq!(
+        HandshakeFailure::from_event(SocketEvent::HANDSHAKE_SUCCEEDED, 0),
+        None
+    );
+});
+
 test!(test_zmq_set_xpub_verbose, {
     let ctx = Context::new();
     let xpub = ctx.socket(XPUB).unwrap();
@@ -561,6 +918,47 @@ test!(test_disconnect, {
     assert_eq!(Error::EAGAIN, sender.send("foo", DONTWAIT).unwrap_err());
 });
 
+test!(test_typed_flags, {
+    let (sender, receiver) = create_socketpair();
+    sender.send("foo", SendFlags::SNDMORE).unwrap();
+    sender.send("bar", SendFlags::empty()).unwrap();
+    assert_eq!(
+        receiver.recv_multipart(RecvFlags::empty()).unwrap(),
+        vec![b"foo".to_vec(), b"bar".to_vec()]
+    );
+    assert_eq!(
+        sender.recv_bytes(RecvFlags::DONTWAIT).unwrap_err(),
+        Error::EAGAIN
+    );
+
+    // The untyped flags convert into their typed counterparts.
+    assert_eq!(SendFlags::from(SNDMORE | DONTWAIT), SendFlags::all());
+    assert_eq!(RecvFlags::from(DONTWAIT), RecvFlags::DONTWAIT);
+    assert_eq!(RecvFlags::from(SNDMORE), RecvFlags::empty());
+});
+
+test!(test_decode_unknown_raw, {
+    assert_eq!(
+        SocketType::try_from_raw(zmq_sys::ZMQ_ROUTER as i32),
+        Some(ROUTER)
+    );
+    // The draft `ZMQ_SERVER` socket type.
+    assert_eq!(SocketType::try_from_raw(12), None);
+    assert_eq!(SocketType::try_from_raw(-1), None);
+
+    assert_eq!(
+        SocketEvent::try_from_raw(zmq_sys::ZMQ_EVENT_CONNECTED as u16),
+        Some(SocketEvent::CONNECTED)
+    );
+    assert_eq!(SocketEvent::try_from_raw(0x8000), None);
+
+    assert_eq!(
+        Mechanism::try_from_raw(zmq_sys::ZMQ_CURVE as i32),
+        Some(Mechanism::ZMQ_CURVE)
+    );
+    assert_eq!(Mechanism::try_from_raw(42), None);
+});
+
 test!(test_disconnect_err, {
     let (sender, _) = create_socketpair();
     // Check that disconnect propagates errors. The endpoint is not connected.
@@ -586,6 +984,362 @@ test!(test_getset_connect_timeout, {
     }
 });
 
+test!(test_getset_invert_matching, {
+    let ctx = Context::new();
+    let sock = ctx.socket(SUB).unwrap();
+    assert!(!sock.is_invert_matching().unwrap());
+    sock.set_invert_matching(true).unwrap();
+    assert!(sock.is_invert_matching().unwrap());
+});
+
+test!(test_invert_matching, {
+    let ctx = Context::new();
+    let publisher = ctx.socket(PUB).unwrap();
+    let subscriber = ctx.socket(SUB).unwrap();
+    // The option needs to be set on both sides.
+    publisher.set_invert_matching(true).unwrap();
+    publisher.bind("inproc://invert_matching").unwrap();
+    subscriber.set_invert_matching(true).unwrap();
+    subscriber.set_subscribe(b"skip").unwrap();
+    subscriber.connect("inproc://invert_matching").unwrap();
+
+    // Subscriptions are propagated asynchronously; repeat until the
+    // first matching message arrives.
+    loop {
+        publisher.send("skip this", 0).unwrap();
+        publisher.send("keep this", 0).unwrap();
+        if subscriber.poll(POLLIN, 100).unwrap() != 0 {
+            break;
+        }
+    }
+    assert_eq!(subscriber.recv_bytes(0).unwrap(), b"keep this");
+});
+
+test!(test_is_thread_safe, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+    assert!(!sock.is_thread_safe().unwrap());
+});
+
+test!(test_set_write_only_options, {
+    let ctx = Context::new();
+    let router = ctx.socket(ROUTER).unwrap();
+    router.set_connect_routing_id(b"peer").unwrap();
+    let xpub = ctx.socket(XPUB).unwrap();
+    xpub.set_xpub_nodrop(true).unwrap();
+    xpub.set_xpub_manual(true).unwrap();
+    xpub.set_xpub_verboser(true).unwrap();
+    let stream = ctx.socket(STREAM).unwrap();
+    stream.set_stream_notify(false).unwrap();
+    let raw = ctx.socket(ROUTER).unwrap();
+    raw.set_router_raw(true).unwrap();
+});
+
+test!(test_connect_routing_id, {
+    let ctx = Context::new();
+    let router = ctx.socket(ROUTER).unwrap();
+    let dealer = ctx.socket(DEALER).unwrap();
+    dealer.bind("inproc://connect_routing_id").unwrap();
+    router.set_connect_routing_id(b"dealer").unwrap();
+    router.connect("inproc://connect_routing_id").unwrap();
+    router
+        .send_multipart([&b"dealer"[..], b"hello"], 0)
+        .unwrap();
+    assert_eq!(dealer.recv_bytes(0).unwrap(), b"hello");
+});
+
+test!(test_getset_tcp_maxrt, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+    sock.set_tcp_maxrt(20000).unwrap();
+    assert_eq!(sock.get_tcp_maxrt().unwrap(), 20000);
+});
+
+test!(test_getset_multicast_maxtpdu, {
+    let ctx = Context::new();
+    let sock = ctx.socket(PUB).unwrap();
+    sock.set_multicast_maxtpdu(1200).unwrap();
+    assert_eq!(sock.get_multicast_maxtpdu().unwrap(), 1200);
+});
+
+test!(test_getset_use_fd, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REP).unwrap();
+    assert_eq!(sock.get_use_fd().unwrap(), -1);
+    sock.set_use_fd(42).unwrap();
+    assert_eq!(sock.get_use_fd().unwrap(), 42);
+});
+
+test!(test_getset_bindtodevice, {
+    if version_ge_4_3() {
+        let ctx = Context::new();
+        let sock = ctx.socket(REP).unwrap();
+        sock.set_bindtodevice("lo").unwrap();
+        assert_eq!(sock.get_bindtodevice().unwrap().unwrap(), "lo");
+    }
+});
+
+test!(test_getset_vmci, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+    // The VMCI options are only available if libzmq was built with
+    // support for the VMCI transport.
+    if sock.set_vmci_buffer_size(4096) != Err(Error::EINVAL) {
+        assert_eq!(sock.get_vmci_buffer_size().unwrap(), 4096);
+        sock.set_vmci_buffer_min_size(1024).unwrap();
+        assert_eq!(sock.get_vmci_buffer_min_size().unwrap(), 1024);
+        sock.set_vmci_buffer_max_size(8192).unwrap();
+        assert_eq!(sock.get_vmci_buffer_max_size().unwrap(), 8192);
+        sock.set_vmci_connect_timeout(500).unwrap();
+        assert_eq!(sock.get_vmci_connect_timeout().unwrap(), 500);
+    }
+});
+
+test!(test_getset_timeouts_duration, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+    assert_eq!(sock.get_rcvtimeo_duration().unwrap(), None);
+    sock.set_rcvtimeo_duration(Some(Duration::from_secs(2)))
+        .unwrap();
+    assert_eq!(sock.get_rcvtimeo().unwrap(), 2000);
+    assert_eq!(
+        sock.get_rcvtimeo_duration().unwrap(),
+        Some(Duration::from_secs(2))
+    );
+    sock.set_rcvtimeo_duration(None).unwrap();
+    assert_eq!(sock.get_rcvtimeo().unwrap(), -1);
+
+    // Zero is a valid timeout, making the operation non-blocking.
+    sock.set_sndtimeo_duration(Some(Duration::from_secs(0)))
+        .unwrap();
+    assert_eq!(
+        sock.get_sndtimeo_duration().unwrap(),
+        Some(Duration::from_secs(0))
+    );
+
+    sock.set_linger_duration(None).unwrap();
+    assert_eq!(sock.get_linger().unwrap(), -1);
+    sock.set_reconnect_ivl_duration(None).unwrap();
+    assert_eq!(sock.get_reconnect_ivl().unwrap(), -1);
+});
+
+test!(test_set_duration_rounding, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+    // Sub-millisecond durations are rounded up, rather than turning
+    // into a non-blocking timeout.
+    sock.set_rcvtimeo_duration(Some(Duration::from_micros(1)))
+        .unwrap();
+    assert_eq!(sock.get_rcvtimeo().unwrap(), 1);
+    sock.set_rcvtimeo_duration(Some(Duration::new(1, 500_000)))
+        .unwrap();
+    assert_eq!(sock.get_rcvtimeo().unwrap(), 1001);
+});
+
+test!(test_set_duration_overflow, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+    sock.set_rcvtimeo(1000).unwrap();
+    let too_long = Duration::from_millis(i32::max_value() as u64 + 1);
+    assert_eq!(
+        sock.set_rcvtimeo_duration(Some(too_long)),
+        Err(Error::EINVAL)
+    );
+    assert_eq!(
+        sock.set_rcvtimeo_duration(Some(Duration::from_secs(u64::max_value()))),
+        Err(Error::EINVAL)
+    );
+    assert_eq!(sock.get_rcvtimeo().unwrap(), 1000);
+});
+
+test!(test_getset_disabled_duration, {
+    let ctx = Context::new();
+    let sock = ctx.socket(REQ).unwrap();
+    sock.set_handshake_ivl_duration(None).unwrap();
+    assert_eq!(sock.get_handshake_ivl().unwrap(), 0);
+    assert_eq!(sock.get_handshake_ivl_duration().unwrap(), None);
+    // For options where zero disables the limit, a zero duration would
+    // be ambiguous.
+    assert_eq!(
+        sock.set_handshake_ivl_duration(Some(Duration::from_secs(0))),
+        Err(Error::EINVAL)
+    );
+    sock.set_handshake_ivl_duration(Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(sock.get_handshake_ivl().unwrap(), 5000);
+
+    sock.set_heartbeat_ivl_duration(Some(Duration::from_millis(250)))
+        .unwrap();
+    assert_eq!(
+        sock.get_heartbeat_ivl_duration().unwrap(),
+        Some(Duration::from_millis(250))
+    );
+    sock.set_heartbeat_ttl_duration(Some(Duration::from_secs(3)))
+        .unwrap();
+    assert_eq!(
+        sock.get_heartbeat_ttl_duration().unwrap(),
+        Some(Duration::from_secs(3))
+    );
+    assert_eq!(sock.get_heartbeat_timeout_duration().unwrap(), None);
+    sock.set_heartbeat_timeout_duration(Some(Duration::from_secs(2)))
+        .unwrap();
+    assert_eq!(sock.get_heartbeat_timeout().unwrap(), 2000);
+    sock.set_reconnect_ivl_max_duration(Some(Duration::from_secs(10)))
+        .unwrap();
+    assert_eq!(
+        sock.get_reconnect_ivl_max_duration().unwrap(),
+        Some(Duration::from_secs(10))
+    );
+    sock.set_reconnect_ivl_max_duration(None).unwrap();
+    assert_eq!(sock.get_reconnect_ivl_max().unwrap(), 0);
+    sock.set_recovery_ivl_duration(Duration::from_secs(3))
+        .unwrap();
+    assert_eq!(
+        sock.get_recovery_ivl_duration().unwrap(),
+        Duration::from_secs(3)
+    );
+    if version_ge_4_2() {
+        sock.set_tcp_maxrt_duration(Some(Duration::from_secs(20)))
+            .unwrap();
+        assert_eq!(sock.get_tcp_maxrt().unwrap(), 20000);
+        sock.set_tcp_maxrt_duration(None).unwrap();
+        assert_eq!(sock.get_tcp_maxrt_duration().unwrap(), None);
+        sock.set_connect_timeout_duration(Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(sock.get_connect_timeout().unwrap(), 1000);
+        sock.set_connect_timeout_duration(None).unwrap();
+        assert_eq!(sock.get_connect_timeout_duration().unwrap(), None);
+    }
+});
+
+test!(test_poll_duration, {
+    let ctx = Context::new();
+    let sock = ctx.socket(PULL).unwrap();
+    sock.bind("inproc://poll_duration").unwrap();
+    let start = Instant::now();
+    assert_eq!(
+        sock.poll_duration(POLLIN, Some(Duration::from_millis(50)))
+            .unwrap(),
+        0
+    );
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    let push = ctx.socket(PUSH).unwrap();
+    push.connect("inproc://poll_duration").unwrap();
+    push.send("ready", 0).unwrap();
+    assert_eq!(sock.poll_duration(POLLIN, None).unwrap(), 1);
+
+    let mut items = [sock.as_poll_item(POLLIN)];
+    assert_eq!(
+        poll_duration(&mut items, Some(Duration::from_secs(u64::max_value()))).unwrap(),
+        1
+    );
+    assert!(items[0].is_readable());
+});
+
+test!(test_options_snapshot, {
+    let ctx = Context::new();
+    let sock = ctx.socket(DEALER).unwrap();
+    sock.set_sndhwm(42).unwrap();
+    sock.set_linger(0).unwrap();
+    sock.set_identity(b"dealer").unwrap();
+    sock.set_plain_username(Some("billybob")).unwrap();
+    sock.set_plain_password(Some("m00c0w")).unwrap();
+    sock.bind("inproc://options_snapshot").unwrap();
+
+    let options = sock.options_snapshot().unwrap();
+    assert_eq!(options.socket_type, Some(DEALER));
+    assert_eq!(
+        options.last_endpoint.as_ref().unwrap(),
+        "inproc://options_snapshot"
+    );
+    assert_eq!(options.mechanism, Some(Mechanism::ZMQ_PLAIN));
+    assert_eq!(options.routing_id.as_ref().unwrap(), b"dealer");
+    assert_eq!(options.sndhwm, Some(42));
+    assert_eq!(options.linger, Some(0));
+    assert_eq!(options.plain_username.as_ref().unwrap(), "billybob");
+    // Options libzmq cannot read back are skipped.
+    assert_eq!(options.router_mandatory, None);
+    assert!(!format!("{:?}", options).contains("m00c0w"));
+});
+
+test!(test_socket_debug, {
+    let ctx = Context::new();
+    let sock = ctx.socket(PUSH).unwrap();
+    sock.set_sndhwm(42).unwrap();
+    sock.bind("inproc://socket_debug").unwrap();
+    let debug = format!("{:?}", sock);
+    assert!(debug.starts_with("Socket {"));
+    assert!(debug.contains("type: PUSH"));
+    assert!(debug.contains("last_endpoint: \"inproc://socket_debug\""));
+    assert!(debug.contains("sndhwm: 42"));
+});
+
+test!(test_endpoints, {
+    let ctx = Context::new();
+    let sock = ctx.socket(DEALER).unwrap();
+    assert!(sock.endpoints().is_empty());
+
+    sock.bind("tcp://127.0.0.1:*").unwrap();
+    let tcp = sock.get_last_endpoint().unwrap().unwrap();
+    assert!(!tcp.ends_with(":*"));
+    sock.bind("inproc://endpoints").unwrap();
+    sock.connect("inproc://endpoints_peer").unwrap();
+    assert_eq!(
+        sock.endpoints(),
+        vec![
+            SocketEndpoint::Bound(tcp.clone()),
+            SocketEndpoint::Bound("inproc://endpoints".into()),
+            SocketEndpoint::Connected("inproc://endpoints_peer".into()),
+        ]
+    );
+
+    // Failures leave the recorded endpoints alone.
+    assert_eq!(
+        sock.unbind("inproc://not_bound").unwrap_err(),
+        Error::ENOENT
+    );
+    assert_eq!(sock.endpoints().len(), 3);
+
+    sock.unbind(&tcp).unwrap();
+    sock.disconnect("inproc://endpoints_peer").unwrap();
+    assert_eq!(
+        sock.endpoints(),
+        vec![SocketEndpoint::Bound("inproc://endpoints".into())]
+    );
+    assert_eq!(sock.endpoints()[0].as_str(), "inproc://endpoints");
+});
+
+test!(test_disconnect_all, {
+    let ctx = Context::new();
+    let sock = ctx.socket(PULL).unwrap();
+    sock.bind("inproc://disconnect_all").unwrap();
+    sock.bind("tcp://127.0.0.1:*").unwrap();
+    sock.connect("tcp://127.0.0.1:1").unwrap();
+    sock.disconnect_all().unwrap();
+    assert!(sock.endpoints().is_empty());
+
+    // The endpoint is available again.
+    let other = ctx.socket(PULL).unwrap();
+    other.bind("inproc://disconnect_all").unwrap();
+    sock.bind("inproc://disconnect_all_again").unwrap();
+    assert_eq!(sock.endpoints().len(), 1);
+});
+
+test!(test_disconnect_duplicate, {
+    let ctx = Context::new();
+    let sock = ctx.socket(PUSH).unwrap();
+    sock.connect("tcp://127.0.0.1:1").unwrap();
+    sock.connect("tcp://127.0.0.1:1").unwrap();
+    assert_eq!(sock.endpoints().len(), 2);
+
+    // Like libzmq, a single disconnect detaches all of them.
+    sock.disconnect("tcp://127.0.0.1:1").unwrap();
+    assert!(sock.endpoints().is_empty());
+    sock.connect("tcp://127.0.0.1:1").unwrap();
+    sock.disconnect_all().unwrap();
+});
+
 #[cfg(feature = "compiletest_rs")]
 mod compile {
     extern crate compiletest_rs as compiletest;
@@ -1,8 +1,11 @@
 use quickcheck::{quickcheck, Arbitrary, Gen};
 use rand::Rng;
-use zmq::{z85_decode, z85_encode, DecodeError, EncodeError};
+use zmq::{z85, z85_decode, z85_encode, DecodeError, EncodeError};
 
+use std::ffi::CString;
+use std::io::{Read, Write};
 use std::iter;
+use std::os::raw::c_char;
 
 #[test]
 fn test_z85() {
@@ -26,6 +29,18 @@ fn test_decode_errors() {
         _ => panic!("expected nul error"),
     }
 
+    let bad_str = "/AB~8";
+    match z85_decode(bad_str) {
+        Err(DecodeError::BadCharacter(3)) => (),
+        _ => panic!("expected bad character error"),
+    }
+
+    let bad_str = "%nSc1";
+    match z85_decode(bad_str) {
+        Err(DecodeError::Overflow(0)) => (),
+        _ => panic!("expected overflow error"),
+    }
+
     let bad_bytes = b"\x01\x01\x01\x01\x01";
     match z85_encode(bad_bytes) {
         Err(EncodeError::BadLength) => (),
@@ -33,6 +48,124 @@ fn test_decode_errors() {
     }
 }
 
+#[test]
+fn test_z85_padded() {
+    assert_eq!(z85::encode_padded(b""), "0");
+    assert_eq!(z85::decode_padded("0").unwrap(), b"");
+
+    let decoded = z85_decode("HelloWorld").unwrap();
+    assert_eq!(z85::encode_padded(&decoded), "HelloWorld0");
+    assert_eq!(z85::decode_padded("HelloWorld0").unwrap(), decoded);
+
+    let encoded = z85::encode_padded(b"\x86\x4f\xd2\x6f\xb5");
+    assert_eq!(encoded.len(), z85::padded_encoded_len(5));
+    assert!(encoded.starts_with("Hello"));
+    assert!(encoded.ends_with('3'));
+    assert_eq!(
+        z85::decode_padded(&encoded).unwrap(),
+        b"\x86\x4f\xd2\x6f\xb5"
+    );
+}
+
+#[test]
+fn test_padded_decode_errors() {
+    for bad_str in &["", "Hello", "HelloWorld", "1", "Hello4", "Hello9"] {
+        match z85::decode_padded(bad_str) {
+            Err(DecodeError::BadPadding) => (),
+            r => panic!("expected bad padding error for {:?}, got {:?}", bad_str, r),
+        }
+    }
+
+    // "Hello" decodes to non-zero bytes where padding is expected
+    match z85::decode_padded("Hello1") {
+        Err(DecodeError::BadPadding) => (),
+        r => panic!("expected bad padding error, got {:?}", r),
+    }
+
+    match z85::decode_padded("Hel\"o0") {
+        Err(DecodeError::BadCharacter(3)) => (),
+        r => panic!("expected bad character error, got {:?}", r),
+    }
+}
+
+#[test]
+fn test_into() {
+    let mut encoded = [0u8; 12];
+    assert_eq!(
+        z85::encode_into(b"\x86\x4f\xd2\x6f", &mut encoded).unwrap(),
+        5
+    );
+    assert_eq!(&encoded[..5], b"Hello");
+    match z85::encode_into(b"\x86\x4f\xd2\x6f\xb5", &mut encoded) {
+        Err(EncodeError::BadLength) => (),
+        r => panic!("expected bad length error, got {:?}", r),
+    }
+    assert_eq!(
+        z85::encode_padded_into(b"\x86\x4f\xd2\x6f\xb5", &mut encoded).unwrap(),
+        11
+    );
+    match z85::encode_padded_into(b"\x86\x4f\xd2\x6f\xb5", &mut encoded[..10]) {
+        Err(EncodeError::BufferTooSmall) => (),
+        r => panic!("expected buffer too small error, got {:?}", r),
+    }
+
+    let mut decoded = [0u8; 8];
+    assert_eq!(z85::decode_into("HelloWorld", &mut decoded).unwrap(), 8);
+    assert_eq!(&decoded[..4], b"\x86\x4f\xd2\x6f");
+    match z85::decode_into("HelloWorld", &mut decoded[..7]) {
+        Err(DecodeError::BufferTooSmall) => (),
+        r => panic!("expected buffer too small error, got {:?}", r),
+    }
+    let encoded = std::str::from_utf8(&encoded[..11]).unwrap();
+    assert_eq!(z85::decode_padded_into(encoded, &mut decoded).unwrap(), 5);
+    assert_eq!(&decoded[..5], b"\x86\x4f\xd2\x6f\xb5");
+}
+
+#[test]
+fn test_stream_large() {
+    let input: Vec<u8> = (0..5003).map(|i| (i % 251) as u8).collect();
+    let mut encoder = z85::Encoder::new(Vec::new());
+    for chunk in input.chunks(1000) {
+        encoder.write_all(chunk).unwrap();
+    }
+    let encoded = encoder.finish().unwrap();
+    assert_eq!(encoded, z85::encode_padded(&input).as_bytes());
+
+    let mut decoded = Vec::new();
+    z85::Decoder::new(&encoded[..])
+        .read_to_end(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, input);
+}
+
+#[test]
+fn test_stream_decode_error() {
+    let mut decoder = z85::Decoder::new(&b"HelloWorld"[..]);
+    let err = decoder.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+// The reference implementation of Z85 provided by libzmq.
+fn ffi_z85_encode(data: &[u8]) -> String {
+    let mut dest = vec![0u8; data.len() * 5 / 4 + 1];
+    unsafe {
+        zmq_sys::zmq_z85_encode(dest.as_mut_ptr() as *mut c_char, data.as_ptr(), data.len());
+    }
+    dest.pop();
+    String::from_utf8(dest).unwrap()
+}
+
+fn ffi_z85_decode(data: &str) -> Option<Vec<u8>> {
+    let mut dest = vec![0u8; data.len() * 4 / 5];
+    let c_str = CString::new(data).unwrap();
+    let rc = unsafe { zmq_sys::zmq_z85_decode(dest.as_mut_ptr(), c_str.as_ptr()) };
+    if rc.is_null() {
+        None
+    } else {
+        Some(dest)
+    }
+}
+
 // Valid input for z85 encoding (i.e. a slice of bytes with its length
 // being a multiple of 4)
 #[derive(Clone, Debug)]
@@ -45,10 +178,63 @@ impl Arbitrary for Input {
     }
 }
 
+// A single block of characters for Z85 decoding, mostly from the
+// Z85 alphabet, but with the occasional character from outside it.
+#[derive(Clone, Debug)]
+struct Block(String);
+
+impl Arbitrary for Block {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        const ALPHABET: &[u8] =
+            b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+        let chars = (0..5).map(|_| {
+            if g.gen_range(0, 20) == 0 {
+                g.gen_range(1u8, 128) as char
+            } else {
+                ALPHABET[g.gen_range(0, ALPHABET.len())] as char
+            }
+        });
+        Block(chars.collect())
+    }
+}
+
 quickcheck! {
     fn z85_roundtrip(input: Input) -> bool {
         let encoded = z85_encode(&input.0).unwrap();
         let decoded = z85_decode(&encoded).unwrap();
         input.0 == decoded
     }
+
+    fn z85_encode_matches_libzmq(input: Input) -> bool {
+        z85_encode(&input.0).unwrap() == ffi_z85_encode(&input.0)
+    }
+
+    fn z85_decode_matches_libzmq(input: Input) -> bool {
+        let encoded = ffi_z85_encode(&input.0);
+        z85_decode(&encoded).unwrap() == input.0
+    }
+
+    fn z85_decode_validates_like_libzmq(input: Block) -> bool {
+        z85_decode(&input.0).ok() == ffi_z85_decode(&input.0)
+    }
+}
+
+quickcheck! {
+    fn z85_padded_roundtrip(input: Vec<u8>) -> bool {
+        let encoded = z85::encode_padded(&input);
+        encoded.len() == z85::padded_encoded_len(input.len())
+            && z85::decode_padded(&encoded).unwrap() == input
+    }
+
+    fn z85_stream_roundtrip(input: Vec<u8>, chunk_size: usize) -> bool {
+        let chunk_size = chunk_size % 7 + 1;
+        let mut encoder = z85::Encoder::new(Vec::new());
+        for chunk in input.chunks(chunk_size) {
+            encoder.write_all(chunk).unwrap();
+        }
+        let encoded = encoder.finish().unwrap();
+        let mut decoded = Vec::new();
+        z85::Decoder::new(&encoded[..]).read_to_end(&mut decoded).unwrap();
+        encoded == z85::encode_padded(&input).as_bytes() && decoded == input
+    }
 }
@@ -3,7 +3,7 @@ use libc as errno;
 #[cfg(windows)]
 use windows::errno;
 
-const ZMQ_HAUSNUMERO: i32 = 156_384_712;
+pub const ZMQ_HAUSNUMERO: i32 = 156_384_712;
 
 pub const EACCES:           i32 = errno::EACCES;
 pub const EADDRINUSE:       i32 = errno::EADDRINUSE;
@@ -109,6 +109,8 @@ pub const ZMQ_USE_FD: u32 = 89;
 pub const ZMQ_GSSAPI_PRINCIPAL_NAMETYPE: u32 = 90;
 pub const ZMQ_GSSAPI_SERVICE_PRINCIPAL_NAMETYPE: u32 = 91;
 pub const ZMQ_BINDTODEVICE: u32 = 92;
+pub const ZMQ_ZAP_ENFORCE_DOMAIN: u32 = 93;
+pub const ZMQ_METADATA: u32 = 95;
 pub const ZMQ_MORE: u32 = 1;
 pub const ZMQ_SHARED: u32 = 3;
 pub const ZMQ_DONTWAIT: u32 = 1;
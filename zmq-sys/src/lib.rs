@@ -122,7 +122,12 @@ pub use crate::ffi::{
     ZMQ_USE_FD,
     ZMQ_GSSAPI_PRINCIPAL_NAMETYPE,
     ZMQ_GSSAPI_SERVICE_PRINCIPAL_NAMETYPE,
+    ZMQ_GSSAPI_NT_HOSTBASED,
+    ZMQ_GSSAPI_NT_USER_NAME,
+    ZMQ_GSSAPI_NT_KRB5_PRINCIPAL,
     ZMQ_BINDTODEVICE,
+    ZMQ_ZAP_ENFORCE_DOMAIN,
+    ZMQ_METADATA,
     ZMQ_MORE,
     ZMQ_SHARED,
     ZMQ_DONTWAIT,